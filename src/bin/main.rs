@@ -304,18 +304,34 @@
 // 	Ok(())
 // }
 
-use std::path::Path;
-
-use clap::{value_parser, Arg, ArgMatches};
-use directories::ProjectDirs;
+use clap::{value_parser, Arg, ArgAction, ArgGroup, ArgMatches};
 use log::{debug, LevelFilter};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 
 use cor_args::{ArgHandler, DefaultHandler, EnvHandler, FileHandler, Handler};
 use prettytable::{color, format, Attr, Cell, Row, Table};
+use terminal_size::{terminal_size, Width};
 use wlm::{
-	default_window_provider,
-	layout::{Format, Layout, LayoutBuilder},
-	shrink_left, shrink_right, WindowProvider,
+	condition::Context,
+	default_window_provider, display_width,
+	export::{export, resolve, Target},
+	fingerprint::fit,
+	import::{import, Tool},
+	journal::Journal,
+	layout::{
+		Desktop, Format, Layout, LayoutBuilder, Screen, Window, WindowBuilder, LAYOUT_EXTENSIONS,
+	},
+	library::{example_layout, layout_format, read_layout, starter_layout, Library, Source},
+	listing::{
+		field as listing_field, fit_widths, list, select, Column, FilterBuilder, SortKey, Template,
+		DEFAULT_COLUMNS,
+	},
+	shrink_left, shrink_right,
+	snapshot::{rule_for, snapshot},
+	validate::{Diagnostic, Severity},
+	WindowProvider,
 };
 
 /// Sets up logging based on the specified verbosity level.
@@ -359,105 +375,1116 @@ fn setup_logging(verbosity: &str) {
 		.init();
 }
 
-fn ls_table() {
+/// Prints the windows on `screens` as a table of `columns`, shrinking the widest columns so the
+/// table fits `width` columns of the terminal, if limited.
+fn ls_table(screens: Vec<Screen>, columns: &[Column], width: Option<usize>) {
+	let rows = screens
+		.iter()
+		.flat_map(|s| s.windows.iter().map(move |w| (s, w)))
+		.map(|(s, w)| {
+			columns
+				.iter()
+				.map(|column| match column.field.as_str() {
+					"desktop" if w.sticky == Some(true) => "all".to_string(),
+					field => listing_field(s, w, field),
+				})
+				.collect::<Vec<String>>()
+		})
+		.collect::<Vec<Vec<String>>>();
+	let headers = columns
+		.iter()
+		.map(|column| {
+			let mut header = column.field.clone();
+			header[..1].make_ascii_uppercase();
+			header
+		})
+		.collect::<Vec<String>>();
+	let natural = columns
+		.iter()
+		.enumerate()
+		.map(|(i, column)| {
+			let widest = rows
+				.iter()
+				.map(|row| display_width(&row[i]))
+				.chain([display_width(&headers[i])])
+				.max()
+				.unwrap_or(0);
+			column.width.map_or(widest, |width| widest.min(width))
+		})
+		.collect::<Vec<usize>>();
+	// The clean format pads each cell with a space on either side.
+	let widths = match width {
+		Some(width) => fit_widths(&natural, width.saturating_sub(2), 2),
+		None => natural,
+	};
+
+	let mut table = Table::new();
+	table.set_format(*format::consts::FORMAT_CLEAN);
+	table.add_row(Row::new(
+		headers
+			.iter()
+			.zip(&widths)
+			.map(|(header, width)| Cell::new(&shrink_right(header, *width)).style_spec("c"))
+			.collect(),
+	));
+	for row in rows {
+		table.add_row(Row::new(
+			row.iter()
+				.zip(columns.iter().zip(&widths))
+				.map(|(value, (column, width))| match column.field.as_str() {
+					"title" => Cell::new(&shrink_right(value, *width))
+						.with_style(Attr::ForegroundColor(color::RED)),
+					"process" => Cell::new(&shrink_left(value, *width))
+						.with_style(Attr::ForegroundColor(color::GREEN)),
+					"desktop" | "state" => Cell::new(&shrink_right(value, *width)).style_spec("c"),
+					_ => Cell::new(&shrink_right(value, *width)),
+				})
+				.collect(),
+		));
+	}
+	table.printstd();
+}
+
+fn ls_layout(
+	out: &'_ mut dyn std::io::Write,
+	screens: Vec<Screen>,
+	format: Format,
+) -> Result<(), String> {
+	let layout = LayoutBuilder::default().screens(screens).build().unwrap();
+	write!(out, "{}", layout.serialize(format)?)
+		.map_err(|e| format!("Failed writing {} output: {}", format, e))
+}
+
+fn ls(matches: &ArgMatches) -> Result<(), String> {
+	let format = matches
+		.get_one::<Format>("format")
+		.unwrap_or(&Format::Table);
+	if matches.contains_id("columns") && format != &Format::Table {
+		return Err("--columns only applies to the table format".to_string());
+	}
+	let columns = Column::parse_list(
+		matches
+			.get_one::<String>("columns")
+			.map_or(DEFAULT_COLUMNS, String::as_str),
+	)?;
+	let template = matches
+		.get_one::<String>("template")
+		.map(|template| Template::parse(template))
+		.transpose()?;
+	let provider = default_window_provider();
+	let desktop =
+		matches
+			.get_one::<String>("desktop")
+			.map(|desktop| match desktop.parse::<u32>() {
+				Ok(index) => Desktop::from(index),
+				Err(_) => Desktop::from(desktop.as_str()),
+			});
+	let filter = FilterBuilder::default()
+		.title(matches.get_one::<String>("title").cloned())
+		.process(matches.get_one::<String>("process").cloned())
+		.screen(matches.get_one::<String>("screen").cloned())
+		.desktop_names(match desktop {
+			Some(Desktop::Name(_)) => provider.desktop_names(),
+			_ => Vec::new(),
+		})
+		.desktop(desktop)
+		.build()
+		.unwrap();
+	filter.check()?;
+	let screens = select(
+		provider.screens(),
+		&filter,
+		matches.get_one::<SortKey>("sort").copied(),
+	);
+	if let Some(template) = template {
+		for screen in &screens {
+			for window in &screen.windows {
+				println!("{}", template.render(screen, window));
+			}
+		}
+		return Ok(());
+	}
+	log::debug!("Format: {}", format);
+	match format {
+		Format::Table => {
+			let width = match matches.get_one::<usize>("width") {
+				Some(0) => None,
+				Some(width) => Some(*width),
+				None => terminal_size().map(|(Width(width), _)| usize::from(width)),
+			};
+			ls_table(screens, &columns, width)
+		}
+		Format::Ndjson | Format::Csv | Format::Tsv => print!("{}", list(&screens, *format)?),
+		format => ls_layout(&mut std::io::stdout(), screens, *format)?,
+	}
+	Ok(())
+}
+
+/// Lists the connected monitors, without their windows.
+fn monitors(matches: &ArgMatches) -> Result<(), String> {
+	let format = *matches
+		.get_one::<Format>("format")
+		.unwrap_or(&Format::Table);
+	let screens = default_window_provider()
+		.screens()
+		.into_iter()
+		.map(|screen| Screen {
+			windows: Vec::new(),
+			..screen
+		})
+		.collect::<Vec<Screen>>();
+	match format {
+		Format::Table => monitors_table(&screens),
+		Format::Ndjson | Format::Csv | Format::Tsv => {
+			return Err(format!("Monitors cannot be listed as {}", format))
+		}
+		format => ls_layout(&mut std::io::stdout(), screens, format)?,
+	}
+	Ok(())
+}
+
+fn monitors_table(screens: &[Screen]) {
+	let text = |value: Option<String>| value.unwrap_or_default();
+	let mut table = Table::new();
+	table.set_format(*format::consts::FORMAT_CLEAN);
+	table.add_row(Row::new(
+		[
+			"Id",
+			"Name",
+			"Geometry",
+			"Work area",
+			"Primary",
+			"DPI",
+			"Scale",
+			"Rotation",
+		]
+		.iter()
+		.map(|header| Cell::new(header).style_spec("c"))
+		.collect(),
+	));
+	for screen in screens {
+		table.add_row(Row::new(vec![
+			Cell::new(&text(screen.id.map(|id| id.to_string()))).style_spec("c"),
+			Cell::new(&text(screen.name.clone())).with_style(Attr::ForegroundColor(color::GREEN)),
+			Cell::new(&text(screen.bounds.map(|b| b.to_string()))),
+			Cell::new(&text(screen.work_area.map(|a| a.to_string()))),
+			Cell::new(if screen.primary == Some(true) {
+				"yes"
+			} else {
+				""
+			})
+			.style_spec("c"),
+			Cell::new(&text(screen.dpi.map(|dpi| dpi.to_string()))).style_spec("r"),
+			Cell::new(&text(screen.scale.map(|scale| format!("{}x", scale)))).style_spec("r"),
+			Cell::new(&text(
+				screen.rotation.map(|degrees| format!("{}°", degrees)),
+			))
+			.style_spec("r"),
+		]));
+	}
+	table.printstd();
+}
+
+/// Returns the layout library searched via `--layout-path` or `WLM_LAYOUT_PATH`.
+fn library(matches: &ArgMatches) -> Library {
+	let library = match matches.get_one::<String>("layout_path") {
+		Some(search_path) => Library::from_search_path(search_path),
+		None => Library::default(),
+	};
+	log::debug!("WLM_LAYOUT_PATH: {:?}", library.dirs());
+	library
+}
+
+/// Loads the layout given on the command line, or via `WLM_LAYOUT_NAME`, falling back to the
+/// `default` layout.
+///
+/// An explicitly given layout must exist, whereas a missing `default` layout is an empty one.
+///
+/// Screens and rules whose `when` condition does not hold on this machine are dropped, then the
+/// variables given via `--var` override those of the layout before they are interpolated.
+fn load_layout(matches: &ArgMatches) -> Result<Layout, String> {
+	let library = library(matches);
+	let source = match auto_layout(matches, &library)? {
+		Some(name) => Some(Source::Name(name)),
+		None => matches.get_one::<String>("layout").map(Source::parse),
+	};
+	let format = matches.get_one::<Format>("format").unwrap_or(&Format::Yaml);
+	let layout = load_source_or_default(&library, source, *format)?;
+	prepare_layout(layout, default_window_provider().screens(), matches)
+}
+
+/// Loads the layout from `source`, reading one on stdin as `format`, or the `default` layout
+/// without a source, which is an empty one when missing.
+fn load_source_or_default(
+	library: &Library,
+	source: Option<Source>,
+	format: Format,
+) -> Result<Layout, String> {
+	match source {
+		Some(source) => {
+			log::debug!("layout = {:?}", source);
+			library.load_source(&source, &mut std::io::stdin(), format)
+		}
+		None => Ok(library.load("default")?.unwrap_or_default()),
+	}
+}
+
+/// Prepares `layout` to be applied to the connected `screens`: drops the screens and rules whose
+/// `when` condition does not hold, then interpolates variables, those given via `--var` first.
+fn prepare_layout(
+	layout: Layout,
+	screens: Vec<Screen>,
+	matches: &ArgMatches,
+) -> Result<Layout, String> {
+	let mut layout = layout.select(&Context::current(screens))?;
+	layout.vars.extend(vars(matches));
+	layout.interpolate()
+}
+
+/// Returns the name of the layout fitting the connected displays best if `--auto` is given,
+/// falling back to the `default` layout if none fits.
+fn auto_layout(matches: &ArgMatches, library: &Library) -> Result<Option<String>, String> {
+	if !matches.get_flag("auto") {
+		return Ok(None);
+	}
+	let screens = default_window_provider().screens();
+	match library.find_for_displays(&screens) {
+		Some(name) => {
+			log::info!("Picked layout '{}' for the connected displays", name);
+			Ok(Some(name))
+		}
+		None if library.find("default")?.is_some() => {
+			log::info!("No layout matches the connected displays, using 'default'");
+			Ok(Some("default".to_string()))
+		}
+		None => Err("No layout matches the connected displays".to_string()),
+	}
+}
+
+/// Returns the variables given via `--var NAME=VALUE`.
+fn vars(matches: &ArgMatches) -> BTreeMap<String, String> {
+	matches
+		.get_many::<(String, String)>("var")
+		.map(|vars| vars.cloned().collect())
+		.unwrap_or_default()
+}
+
+fn parse_var(var: &str) -> Result<(String, String), String> {
+	var.split_once('=')
+		.map(|(name, value)| (name.to_string(), value.to_string()))
+		.ok_or_else(|| format!("Invalid variable '{}', expected NAME=VALUE", var))
+}
+
+fn var_arg() -> Arg {
+	Arg::new("var")
+		.help("Set a variable of the layout")
+		.long("var")
+		.value_name("NAME=VALUE")
+		.value_parser(parse_var)
+		.action(ArgAction::Append)
+		.required(false)
+}
+
+fn layout(matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+	println!("Running layout: {:?}", matches);
+	let mut layout = load_layout(matches)?;
+	if let Some(rule) = matches.get_one::<String>("focus") {
+		layout.focus(rule)?;
+	}
+	debug!("Applying layout: {:?}", layout);
+	let provider = default_window_provider();
+	if let Err(e) = update_journal(|journal| {
+		journal.record(layout.clone(), &provider.screens());
+		Ok(())
+	}) {
+		log::warn!("Failed to record the layout to undo it: {}", e);
+	}
+	provider.layout(&layout);
+	Ok(())
+}
+
+/// Loads the journal of applied layouts, lets `update` change it and keeps it again.
+fn update_journal<F, T>(update: F) -> Result<T, String>
+where
+	F: FnOnce(&mut Journal) -> Result<T, String>,
+{
+	let journal_file = Journal::default_file().ok_or("No data directory to keep the journal in")?;
+	let mut journal = Journal::load(&journal_file)?;
+	let result = update(&mut journal)?;
+	journal.save(&journal_file)?;
+	Ok(result)
+}
+
+/// Moves the windows affected by the latest applied layout back to where they were.
+fn undo() -> Result<(), String> {
+	let provider = default_window_provider();
+	let screens = provider.screens();
+	let previous =
+		update_journal(|journal| journal.undo(&screens).ok_or("Nothing to undo".to_string()))?;
+	debug!("Applying layout: {:?}", previous);
+	provider.layout(&previous);
+	Ok(())
+}
+
+/// Applies the latest undone layout again.
+fn redo() -> Result<(), String> {
+	let applied = update_journal(|journal| journal.redo().ok_or("Nothing to redo".to_string()))?;
+	debug!("Applying layout: {:?}", applied);
+	default_window_provider().layout(&applied);
+	Ok(())
+}
+
+/// Shows how the windows on screen differ from a layout, or how two layouts differ, returning
+/// whether they differ.
+fn diff(matches: &ArgMatches) -> Result<bool, String> {
+	let library = library(matches);
+	let load = |layout: Option<&String>| {
+		load_source_or_default(&library, layout.map(Source::parse), Format::Yaml)
+	};
+	let layout = load(matches.get_one::<String>("layout"))?;
+	let format = *matches
+		.get_one::<Format>("format")
+		.unwrap_or(&Format::Table);
+	if let Some(other) = matches.get_one::<String>("other") {
+		let changes = wlm::diff::compare(&layout, &load(Some(other))?);
+		match format {
+			Format::Table if changes.is_empty() => {}
+			Format::Table => diff_layouts_table(&changes),
+			format => print!("{}", format.serialize(&changes)?),
+		}
+		return Ok(!changes.is_empty());
+	}
+
+	let screens = default_window_provider().screens();
+	let layout = prepare_layout(layout, screens.clone(), matches)?;
+	let plan = wlm::diff::plan(&layout, &screens)?;
+	match format {
+		Format::Table => diff_plan_table(&plan),
+		format => print!("{}", format.serialize(&plan)?),
+	}
+	Ok(plan.drifted())
+}
+
+fn export_layout(matches: &ArgMatches) -> Result<(), String> {
+	let layout = load_source_or_default(
+		&library(matches),
+		matches.get_one::<String>("layout").map(Source::parse),
+		Format::Yaml,
+	)?;
+	let screens = default_window_provider().screens();
+	let layout = prepare_layout(layout, screens.clone(), matches)?;
+	let target = *matches.get_one::<Target>("to").unwrap();
+	print!("{}", export(&resolve(&layout, &screens)?, target));
+	Ok(())
+}
+
+fn diff_plan_table(plan: &wlm::diff::Plan) {
 	let mut table = Table::new();
 	table.set_format(*format::consts::FORMAT_CLEAN);
 	table.add_row(Row::new(vec![
-		Cell::new("Title").style_spec("c"),
-		Cell::new("Process").style_spec("c"),
-		Cell::new("Point").style_spec("l"),
-		Cell::new("Dimension").style_spec("l"),
+		Cell::new(""),
+		Cell::new("Rule"),
+		Cell::new("Window"),
+		Cell::new("Target"),
+		Cell::new("Actual"),
 	]));
-	for s in default_window_provider().screens() {
-		for w in s.windows {
+	let rect = |rect: &Option<wlm::layout::Rect>| rect.map(|r| r.to_string()).unwrap_or_default();
+	for step in &plan.steps {
+		let (mark, color) = match (&step.window, step.drifted()) {
+			(None, _) => ("?", color::RED),
+			(Some(_), true) => ("~", color::YELLOW),
+			(Some(_), false) => ("=", color::GREEN),
+		};
+		table.add_row(Row::new(vec![
+			Cell::new(mark).with_style(Attr::ForegroundColor(color)),
+			Cell::new(&step.rule),
+			Cell::new(&shrink_right(
+				step.window.as_deref().unwrap_or("(no window)"),
+				32,
+			)),
+			Cell::new(&rect(&step.target)).with_style(Attr::ForegroundColor(color)),
+			Cell::new(&rect(&step.actual)),
+		]));
+	}
+	for window in &plan.uncovered {
+		table.add_row(Row::new(vec![
+			Cell::new("+").with_style(Attr::ForegroundColor(color::CYAN)),
+			Cell::new("(no rule)"),
+			Cell::new(&shrink_right(&window.window, 32)),
+			Cell::new(""),
+			Cell::new(&rect(&window.actual)),
+		]));
+	}
+	table.printstd();
+}
+
+fn diff_layouts_table(changes: &[wlm::diff::Change]) {
+	use wlm::diff::ChangeKind;
+	let mut table = Table::new();
+	table.set_format(*format::consts::FORMAT_CLEAN);
+	table.add_row(Row::new(vec![
+		Cell::new(""),
+		Cell::new("Where"),
+		Cell::new("Setting"),
+		Cell::new("Old"),
+		Cell::new("New"),
+	]));
+	let value = |value: &Option<serde_json::Value>| match value {
+		Some(serde_json::Value::String(value)) => value.clone(),
+		Some(value) => value.to_string(),
+		None => "".to_string(),
+	};
+	for change in changes {
+		let (mark, color) = match change.kind {
+			ChangeKind::Added => ("+", color::GREEN),
+			ChangeKind::Removed => ("-", color::RED),
+			ChangeKind::Changed => ("~", color::YELLOW),
+		};
+		let mark = Cell::new(mark).with_style(Attr::ForegroundColor(color));
+		if change.fields.is_empty() {
 			table.add_row(Row::new(vec![
-				Cell::new(&shrink_right(
-					w.title.as_ref().unwrap_or(&"".to_string()),
-					32,
-				))
-				.with_style(Attr::ForegroundColor(color::RED)),
-				Cell::new(&shrink_left(
-					w.process.as_ref().unwrap_or(&"".to_string()),
-					64,
-				))
-				.with_style(Attr::ForegroundColor(color::GREEN)),
-				Cell::new(&format!(
-					"({}, {})",
-					w.x.as_ref().unwrap_or(&"0".to_string()),
-					w.y.as_ref().unwrap_or(&"0".to_string())
-				)),
-				Cell::new(&format!(
-					"{} x {}",
-					w.w.as_ref().unwrap_or(&"0".to_string()),
-					w.h.as_ref().unwrap_or(&"0".to_string())
-				)),
+				mark.clone(),
+				Cell::new(&change.at),
+				Cell::new(""),
+				Cell::new(""),
+				Cell::new(""),
+			]));
+		}
+		for (i, field) in change.fields.iter().enumerate() {
+			table.add_row(Row::new(vec![
+				if i == 0 { mark.clone() } else { Cell::new("") },
+				Cell::new(if i == 0 { &change.at } else { "" }),
+				Cell::new(&field.field),
+				Cell::new(&value(&field.old)).with_style(Attr::ForegroundColor(color::RED)),
+				Cell::new(&value(&field.new)).with_style(Attr::ForegroundColor(color::GREEN)),
 			]));
 		}
 	}
 	table.printstd();
 }
 
-fn ls_yaml(out: &'_ mut dyn std::io::Write) {
+/// Checks the layout given on the command line without touching any windows, reporting every
+/// problem found along with where it was found.
+fn validate(matches: &ArgMatches) -> Result<(), String> {
+	let library = library(matches);
+	let layout = matches
+		.get_one::<String>("layout")
+		.map(Source::parse)
+		.unwrap_or_else(|| Source::Name("default".to_string()));
+	let (label, layout_file, format) = match &layout {
+		Source::Stdin => (
+			"<stdin>".to_string(),
+			None,
+			*matches.get_one::<Format>("format").unwrap_or(&Format::Yaml),
+		),
+		Source::File(layout_file) => (
+			layout_file.display().to_string(),
+			Some(layout_file.clone()),
+			layout_format(layout_file)?,
+		),
+		Source::Name(name) => match library.find(name)? {
+			Some(layout_file) => (
+				layout_file.display().to_string(),
+				Some(layout_file.clone()),
+				layout_format(&layout_file)?,
+			),
+			None => return Err(format!("No layout named '{}'", name)),
+		},
+	};
+	let mut content = String::new();
+	match &layout_file {
+		Some(layout_file) => {
+			content = std::fs::read_to_string(layout_file)
+				.map_err(|e| format!("Failed to read {}: {}", label, e))?
+		}
+		None => {
+			std::io::stdin()
+				.read_to_string(&mut content)
+				.map_err(|e| format!("Failed to read layout: {}", e))?;
+		}
+	};
+
+	let vars = vars(matches);
+	let mut diagnostics = wlm::validate::validate(
+		&content,
+		format,
+		&default_window_provider().screens(),
+		&vars,
+	);
+	if !diagnostics.iter().any(|d| d.severity == Severity::Error) {
+		let resolved = match &layout_file {
+			Some(layout_file) => library.load_file(layout_file),
+			None => library.load_reader(&mut content.as_bytes(), format),
+		};
+		let interpolated = resolved.and_then(|mut layout| {
+			layout.vars.extend(vars);
+			layout.interpolate()
+		});
+		if let Err(e) = interpolated {
+			diagnostics.push(Diagnostic::error(e, None));
+		}
+	}
+	for diagnostic in &diagnostics {
+		match diagnostic.location {
+			Some(_) => println!("{}:{}", label, diagnostic),
+			None => println!("{}: {}", label, diagnostic),
+		}
+	}
+	let errors = diagnostics
+		.iter()
+		.filter(|d| d.severity == Severity::Error)
+		.count();
+	match errors {
+		0 => Ok(()),
+		1 => Err(format!("{} has 1 error", label)),
+		n => Err(format!("{} has {} errors", label, n)),
+	}
+}
+
+/// Migrates a layout file, e.g. a wlm 0.3 config, to the current layout version, writing it to
+/// `--output` or printing it.
+fn migrate(matches: &ArgMatches) -> Result<(), String> {
+	let layout_file = PathBuf::from(matches.get_one::<String>("file").unwrap());
+	let content = std::fs::read_to_string(&layout_file)
+		.map_err(|e| format!("Failed to read {}: {}", layout_file.display(), e))?;
+	let layout = wlm::migrate::migrate(&content, layout_format(&layout_file)?)
+		.map_err(|e| format!("{}:{}", layout_file.display(), e))?;
+	match matches.get_one::<String>("output").map(PathBuf::from) {
+		Some(output) => {
+			let format = match matches.get_one::<Format>("format") {
+				Some(format) => *format,
+				None => layout_format(&output)?,
+			};
+			std::fs::write(&output, layout.serialize(format)?)
+				.map_err(|e| format!("Failed to write {}: {}", output.display(), e))
+		}
+		None => {
+			let format = match matches.get_one::<Format>("format") {
+				Some(format) => *format,
+				None => layout_format(&layout_file)?,
+			};
+			print!("{}", layout.serialize(format)?);
+			Ok(())
+		}
+	}
+}
+
+fn import_layout(matches: &ArgMatches) -> Result<(), String> {
+	let file = matches.get_one::<String>("file").unwrap();
+	let content = match file.as_str() {
+		"-" => {
+			let mut content = String::new();
+			std::io::stdin()
+				.read_to_string(&mut content)
+				.map_err(|e| format!("Failed to read stdin: {}", e))?;
+			content
+		}
+		file => {
+			std::fs::read_to_string(file).map_err(|e| format!("Failed to read {}: {}", file, e))?
+		}
+	};
+	let tool = *matches.get_one::<Tool>("from").unwrap();
+	let zones = matches.get_one::<String>("zones").map(String::as_str);
+	let layout = import(&content, tool, zones)?;
+	match matches.get_one::<String>("output") {
+		Some(output) => {
+			let (layout_file, format) = new_layout(matches, output)?;
+			write_new_layout(&layout_file, layout.serialize(format)?)
+		}
+		None => {
+			let format = matches.get_one::<Format>("format").unwrap_or(&Format::Yaml);
+			print!("{}", layout.serialize(*format)?);
+			Ok(())
+		}
+	}
+}
+
+/// A layout file opened to be modified and written back in its format.
+struct LayoutFile {
+	path: PathBuf,
+	format: Format,
+	content: String,
+	layout: Layout,
+}
+
+impl LayoutFile {
+	/// Opens the layout file given by the `layout` argument, as a path or the name of a layout.
+	fn open(matches: &ArgMatches) -> Result<Self, String> {
+		let path = match Source::parse(matches.get_one::<String>("layout").unwrap()) {
+			Source::Stdin => return Err("A layout on stdin cannot be modified".to_string()),
+			Source::File(path) => path,
+			Source::Name(name) => library(matches)
+				.find(&name)?
+				.ok_or_else(|| format!("No layout named '{}'", name))?,
+		};
+		let format = layout_format(&path)?;
+		let content = std::fs::read_to_string(&path)
+			.map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+		let layout =
+			Layout::parse(&content, format).map_err(|e| format!("{}:{}", path.display(), e))?;
+		Ok(LayoutFile {
+			path,
+			format,
+			content,
+			layout,
+		})
+	}
+
+	/// Writes `layout` back to the file, refusing to introduce new mistakes while leaving alone
+	/// those it already had, e.g. variables meant to be given via --var.
+	fn save(&self, layout: &Layout) -> Result<(), String> {
+		let modified = layout.serialize(self.format)?;
+		let errors = |content: &str| {
+			wlm::validate::validate(content, self.format, &[], &BTreeMap::new())
+				.into_iter()
+				.filter(|d| d.severity == Severity::Error)
+				.map(|d| d.message)
+				.collect::<Vec<String>>()
+		};
+		let existing = errors(&self.content);
+		if let Some(error) = errors(&modified)
+			.into_iter()
+			.find(|e| !existing.contains(e))
+		{
+			return Err(error);
+		}
+		std::fs::write(&self.path, modified)
+			.map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))
+	}
+}
+
+/// Views or modifies a setting of a layout file addressed by a dotted path, e.g.
+/// `screens.1.windows.0.w`, writing the layout back in the format of its file.
+fn config(matches: &ArgMatches) -> Result<(), String> {
+	let file = LayoutFile::open(matches)?;
+	let path = match matches.get_one::<String>("path") {
+		Some(path) => path,
+		None => {
+			print!("{}", file.layout.serialize(file.format)?);
+			return Ok(());
+		}
+	};
+	let layout = match matches.get_one::<String>("value") {
+		Some(value) => wlm::config::set(&file.layout, path, value)?,
+		None if matches.get_flag("unset") => wlm::config::unset(&file.layout, path)?,
+		None => {
+			match wlm::config::get(&file.layout, path)? {
+				serde_json::Value::String(value) => println!("{}", value),
+				value @ (serde_json::Value::Object(_) | serde_json::Value::Array(_)) => {
+					print!("{}", serde_yaml::to_string(&value).unwrap())
+				}
+				value => println!("{}", value),
+			}
+			return Ok(());
+		}
+	};
+	file.save(&layout)
+		.map_err(|e| format!("Invalid value for '{}': {}", path, e))
+}
+
+/// Appends a rule to a layout for a live window, picked from a list or found by `--title` and
+/// `--process`, placing it where the window currently is.
+fn add(matches: &ArgMatches) -> Result<(), String> {
+	let file = LayoutFile::open(matches)?;
+	let selector = WindowBuilder::default()
+		.title(matches.get_one::<String>("title").cloned())
+		.process(matches.get_one::<String>("process").cloned())
+		.build()
+		.unwrap();
+	let candidates: Vec<(Screen, Window)> = default_window_provider()
+		.screens()
+		.into_iter()
+		.flat_map(|screen| {
+			let windows = screen.windows.clone();
+			windows.into_iter().map(move |w| (screen.clone(), w))
+		})
+		.filter(|(_, window)| {
+			(selector.title.is_none() && selector.process.is_none()) || selector.matches(window)
+		})
+		.collect();
+	let (screen, window) = if matches.get_flag("pick") {
+		pick_window(candidates)?
+	} else {
+		match candidates.len() {
+			0 => return Err("No window matches".to_string()),
+			1 => candidates.into_iter().next().unwrap(),
+			n => {
+				return Err(format!(
+					"{} windows match, narrow down --title and --process or use --pick",
+					n
+				))
+			}
+		}
+	};
+
+	// A rule found by --title or --process keeps matching the way it was found, whereas a picked
+	// window, possibly among those found, is matched by its exact title and process.
+	let mut rule = Window {
+		name: matches.get_one::<String>("name").cloned(),
+		title: selector.title.clone(),
+		process: selector.process.clone(),
+		x: window.x.clone(),
+		y: window.y.clone(),
+		w: window.w.clone(),
+		h: window.h.clone(),
+		..Window::new()
+	};
+	if matches.get_flag("pick") {
+		let picked = rule_for(&window);
+		rule.title = picked.title;
+		rule.process = picked.process;
+	}
+	if matches.get_flag("relative") {
+		let bounds = screen
+			.bounds
+			.ok_or_else(|| "The size of the monitor of the window is unknown".to_string())?;
+		rule = rule.relative_to(&bounds);
+	}
+
+	let mut layout = file.layout.clone();
+	layout.add_rule(screen.id, rule);
+	file.save(&layout)?;
+	println!(
+		"Added {} to {}",
+		window.title.as_deref().unwrap_or("window"),
+		file.path.display()
+	);
+	Ok(())
+}
+
+/// Lists `candidates` and lets the user pick one by its number.
+fn pick_window(candidates: Vec<(Screen, Window)>) -> Result<(Screen, Window), String> {
+	if candidates.is_empty() {
+		return Err("No windows to pick from".to_string());
+	}
+	for (i, (_, window)) in candidates.iter().enumerate() {
+		eprintln!(
+			"{:>3}  {}  {}",
+			i + 1,
+			shrink_right(window.title.as_deref().unwrap_or(""), 48),
+			shrink_left(window.process.as_deref().unwrap_or(""), 32)
+		);
+	}
+	eprint!("Window to add [1-{}]: ", candidates.len());
+	let mut answer = String::new();
+	std::io::stdin()
+		.read_line(&mut answer)
+		.map_err(|e| format!("Failed to read the window to add: {}", e))?;
+	answer
+		.trim()
+		.parse::<usize>()
+		.ok()
+		.filter(|n| (1..=candidates.len()).contains(n))
+		.and_then(|n| candidates.into_iter().nth(n - 1))
+		.ok_or_else(|| format!("No window numbered '{}'", answer.trim()))
+}
+
+/// Returns the file of the layout `name` in the library, or `name` itself if it is a path.
+fn existing_layout(library: &Library, name: &str) -> Result<PathBuf, String> {
+	match Source::parse(name) {
+		Source::Stdin => Err("A layout on stdin is not a layout file".to_string()),
+		Source::File(layout_file) if layout_file.is_file() => Ok(layout_file),
+		Source::File(layout_file) => Err(format!("No layout file {}", layout_file.display())),
+		Source::Name(name) => library
+			.find(&name)?
+			.ok_or_else(|| format!("No layout named '{}'", name)),
+	}
+}
+
+/// Manages the layout files of the library.
+fn layouts(matches: &ArgMatches) -> Result<(), String> {
+	match matches.subcommand() {
+		Some(("list", sub_m)) => layouts_list(sub_m),
+		Some(("show", sub_m)) => {
+			let layout_file =
+				existing_layout(&library(sub_m), sub_m.get_one::<String>("name").unwrap())?;
+			let content = std::fs::read_to_string(&layout_file)
+				.map_err(|e| format!("Failed to read {}: {}", layout_file.display(), e))?;
+			print!("{}", content);
+			Ok(())
+		}
+		Some(("edit", sub_m)) => layouts_edit(sub_m),
+		Some(("cp", sub_m)) => layouts_copy(sub_m, false),
+		Some(("mv", sub_m)) => layouts_copy(sub_m, true),
+		Some(("rm", sub_m)) => {
+			let layout_file =
+				existing_layout(&library(sub_m), sub_m.get_one::<String>("name").unwrap())?;
+			std::fs::remove_file(&layout_file)
+				.map_err(|e| format!("Failed to remove {}: {}", layout_file.display(), e))?;
+			println!("Removed {}", layout_file.display());
+			Ok(())
+		}
+		_ => Err("Invalid subcommand!".to_string()),
+	}
+}
+
+/// Lists the layouts of the library along with how they fit the connected displays.
+fn layouts_list(matches: &ArgMatches) -> Result<(), String> {
+	let library = library(matches);
 	let screens = default_window_provider().screens();
-	let layout = LayoutBuilder::default().screens(screens).build().unwrap();
-	write!(out, "{}", serde_yaml::to_string(&layout).unwrap()).expect("Failed writing YAML output");
+	let mut table = Table::new();
+	table.set_format(*format::consts::FORMAT_CLEAN);
+	table.add_row(Row::new(vec![
+		Cell::new("Name"),
+		Cell::new("Format"),
+		Cell::new("Screens").style_spec("r"),
+		Cell::new("Rules").style_spec("r"),
+		Cell::new("Displays").style_spec("c"),
+		Cell::new("Path"),
+	]));
+	for name in library.names() {
+		let layout_file = match library.find(&name) {
+			Ok(Some(layout_file)) => layout_file,
+			Ok(None) => continue,
+			Err(e) => {
+				log::warn!("{}", e);
+				continue;
+			}
+		};
+		let format = layout_format(&layout_file)?;
+		let (screen_count, rule_count, displays) = match library.load_file(&layout_file) {
+			Ok(layout) => (
+				layout.screens.len().to_string(),
+				layout
+					.screens
+					.iter()
+					.map(|s| s.windows.len())
+					.sum::<usize>()
+					.to_string(),
+				match fit(&layout.match_displays, &screens) {
+					_ if layout.match_displays.is_empty() => "-",
+					Some(fit) if fit.complete => "yes",
+					Some(_) => "partial",
+					None => "no",
+				},
+			),
+			Err(e) => {
+				log::warn!("{}", e);
+				("!".to_string(), "!".to_string(), "!")
+			}
+		};
+		table.add_row(Row::new(vec![
+			Cell::new(&name).with_style(Attr::Bold),
+			Cell::new(format.as_str()),
+			Cell::new(&screen_count).style_spec("r"),
+			Cell::new(&rule_count).style_spec("r"),
+			Cell::new(displays).style_spec("c"),
+			Cell::new(&layout_file.display().to_string()),
+		]));
+	}
+	table.printstd();
+	Ok(())
 }
 
-fn ls(matches: &ArgMatches) {
-	let format = matches
-		.get_one::<Format>("format")
-		.unwrap_or(&Format::Table);
-	log::warn!("Format: {}", format);
-	match format {
-		Format::Table => ls_table(),
-		Format::Yaml => ls_yaml(&mut std::io::stdout()),
+/// Opens a layout in `$VISUAL` or `$EDITOR`, checking it once the editor exits and offering to
+/// edit it again if it has mistakes.
+fn layouts_edit(matches: &ArgMatches) -> Result<(), String> {
+	let layout_file = existing_layout(
+		&library(matches),
+		matches.get_one::<String>("name").unwrap(),
+	)?;
+	let format = layout_format(&layout_file)?;
+	let editor = std::env::var("VISUAL")
+		.or_else(|_| std::env::var("EDITOR"))
+		.unwrap_or_else(|_| if cfg!(windows) { "notepad" } else { "vi" }.to_string());
+	let mut editor = editor.split_whitespace();
+	let program = editor.next().ok_or("No editor set in $VISUAL or $EDITOR")?;
+	let args = editor.collect::<Vec<&str>>();
+	loop {
+		let status = std::process::Command::new(program)
+			.args(&args)
+			.arg(&layout_file)
+			.status()
+			.map_err(|e| format!("Failed to run {}: {}", program, e))?;
+		if !status.success() {
+			return Err(format!("{} exited with {}", program, status));
+		}
+		let content = std::fs::read_to_string(&layout_file)
+			.map_err(|e| format!("Failed to read {}: {}", layout_file.display(), e))?;
+		let diagnostics = wlm::validate::validate(
+			&content,
+			format,
+			&default_window_provider().screens(),
+			&BTreeMap::new(),
+		);
+		for diagnostic in &diagnostics {
+			match diagnostic.location {
+				Some(_) => eprintln!("{}:{}", layout_file.display(), diagnostic),
+				None => eprintln!("{}: {}", layout_file.display(), diagnostic),
+			}
+		}
+		if !diagnostics.iter().any(|d| d.severity == Severity::Error) {
+			return Ok(());
+		}
+		eprint!("The layout has errors, edit it again? [Y/n] ");
+		let mut answer = String::new();
+		std::io::stdin()
+			.read_line(&mut answer)
+			.map_err(|e| format!("Failed to read the answer: {}", e))?;
+		if answer.trim().eq_ignore_ascii_case("n") {
+			return Err(format!("{} has errors", layout_file.display()));
+		}
 	}
 }
 
-fn load_layout<S: AsRef<str>>(name: S, default_layout: Layout) -> Layout {
-	// Determine where to search for layouts via LAYOUT_PATH
-	let layout_path = if let Some(config_dir) = ProjectDirs::from("com", "wlm", "wlm") {
-		config_dir.config_dir().join("layouts")
+/// Copies a layout, or moves it with `remove`, converting it if the destination has another
+/// format. A destination name is in the directory of the layout copied.
+fn layouts_copy(matches: &ArgMatches, remove: bool) -> Result<(), String> {
+	let source = existing_layout(
+		&library(matches),
+		matches.get_one::<String>("name").unwrap(),
+	)?;
+	let format = layout_format(&source)?;
+	let destination = match Source::parse(matches.get_one::<String>("new_name").unwrap()) {
+		Source::Stdin => return Err("A layout cannot be written to stdin".to_string()),
+		Source::File(destination) => destination,
+		// The copy keeps the extension of the layout, e.g. `.yaml` rather than `.yml`.
+		Source::Name(name) => source.with_file_name(format!(
+			"{}.{}",
+			name,
+			source.extension().unwrap().to_string_lossy()
+		)),
+	};
+	// Copying a file onto itself truncates it, and moving it would then remove it.
+	if destination.exists() && destination.canonicalize().ok() == source.canonicalize().ok() {
+		return Err(format!(
+			"{} and {} are the same layout",
+			source.display(),
+			destination.display()
+		));
+	}
+	// A moved layout no longer makes the name of its destination ambiguous.
+	check_new_layout(
+		matches,
+		&destination,
+		Some(&source).filter(|_| remove).map(PathBuf::as_path),
+	)?;
+	let destination_format = layout_format(&destination)?;
+	let written = if destination_format == format && remove {
+		std::fs::rename(&source, &destination)
+	} else if destination_format == format {
+		std::fs::copy(&source, &destination).map(|_| ())
 	} else {
-		Path::new("~/.config/wlm/layouts").into()
+		let layout = read_layout(&source)?;
+		std::fs::write(&destination, layout.serialize(destination_format)?)
 	};
-	log::debug!("WLM_LAYOUT_PATH: {}", layout_path.display());
-
-	let layout_file = Path::new(&layout_path)
-		.join(name.as_ref())
-		.with_extension("yml");
-	if layout_file.exists() {
-		let file = config::File::from(layout_file.as_path());
-		let layout = config::Config::builder()
-			.add_source(file.required(true))
-			.build()
-			.unwrap();
-		let layout = layout
-			.try_deserialize::<Layout>()
-			.expect(&format!("Failed to load layout: {}", layout_file.display()));
-		layout
+	written.map_err(|e| format!("Failed to write {}: {}", destination.display(), e))?;
+	if remove {
+		if source.exists() {
+			std::fs::remove_file(&source)
+				.map_err(|e| format!("Failed to remove {}: {}", source.display(), e))?;
+		}
+		println!("Moved {} to {}", source.display(), destination.display());
 	} else {
-		default_layout
+		println!("Copied {} to {}", source.display(), destination.display());
 	}
+	Ok(())
 }
 
-fn layout(matches: &ArgMatches) {
-	println!("Running layout: {:?}", matches);
-	// Determine the layout to load from LAYOUT_PATH
-	let layout_name = ArgHandler::new(&matches)
-		.next(
-			EnvHandler::new()
-				.prefix("WLM_")
-				.next(DefaultHandler::new("default").into())
-				.into(),
-		)
-		.handle_request("layout_name")
-		.unwrap();
-	log::debug!("layout_name = {}", layout_name);
+/// Returns the file and format of the layout `name` to create, a name in the first directory of
+/// the layout search path or a path, refusing to overwrite an existing one without `--force`.
+fn new_layout(matches: &ArgMatches, name: &str) -> Result<(PathBuf, Format), String> {
+	let (layout_file, format) = match Source::parse(name) {
+		Source::Stdin => return Err("A layout cannot be created on stdin".to_string()),
+		Source::File(layout_file) => {
+			let format = layout_format(&layout_file)?;
+			(layout_file, format)
+		}
+		Source::Name(name) => {
+			let format = *matches.get_one::<Format>("format").unwrap_or(&Format::Yaml);
+			let layout_file = library(matches)
+				.new_layout_file(&name, format)
+				.ok_or_else(|| format!("Layouts cannot be written as {}", format))?;
+			(layout_file, format)
+		}
+	};
+	check_new_layout(matches, &layout_file, None)?;
+	Ok((layout_file, format))
+}
 
-	let layout = load_layout(layout_name, Layout::default());
-	debug!("Applying layout: {:?}", layout);
-	default_window_provider().layout(&layout);
+/// Refuses to overwrite `layout_file` without `--force`, or to write it next to a layout of the
+/// same name with another extension, except `moved`, which would make the name ambiguous.
+fn check_new_layout(
+	matches: &ArgMatches,
+	layout_file: &Path,
+	moved: Option<&Path>,
+) -> Result<(), String> {
+	if layout_file.exists() && !matches.get_flag("force") {
+		return Err(format!(
+			"Layout already exists: {} (use --force to overwrite it)",
+			layout_file.display()
+		));
+	}
+	if let Some(other) = LAYOUT_EXTENSIONS
+		.iter()
+		.map(|ext| layout_file.with_extension(ext))
+		.find(|other| other != layout_file && Some(other.as_path()) != moved && other.is_file())
+	{
+		return Err(format!(
+			"Layout already exists as {}, remove it first",
+			other.display()
+		));
+	}
+	Ok(())
+}
+
+/// Writes `content` to `layout_file`, creating its directory if need be.
+fn write_new_layout(layout_file: &Path, content: String) -> Result<(), String> {
+	if let Some(dir) = layout_file
+		.parent()
+		.filter(|dir| !dir.as_os_str().is_empty())
+	{
+		std::fs::create_dir_all(dir)
+			.map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+	}
+	std::fs::write(layout_file, content)
+		.map_err(|e| format!("Failed to write {}: {}", layout_file.display(), e))?;
+	println!("Created {}", layout_file.display());
+	Ok(())
+}
+
+/// Creates a layout to start from, refusing to overwrite an existing one without `--force`.
+fn init(matches: &ArgMatches) -> Result<(), String> {
+	let name = matches
+		.get_one::<String>("name")
+		.map(String::as_str)
+		.unwrap_or("default");
+	let (layout_file, format) = new_layout(matches, name)?;
+	let layout = if matches.get_flag("current") {
+		snapshot(&default_window_provider().screens(), false)
+	} else {
+		example_layout()
+	};
+	write_new_layout(&layout_file, starter_layout(&layout, format)?)
+}
+
+/// Saves a layout moving the current windows back to where they are, see `snapshot::snapshot`.
+fn snapshot_layout(matches: &ArgMatches) -> Result<(), String> {
+	let (layout_file, format) = new_layout(matches, matches.get_one::<String>("name").unwrap())?;
+	let layout = snapshot(
+		&default_window_provider().screens(),
+		matches.get_flag("relative"),
+	);
+	if layout.screens.is_empty() {
+		log::warn!("No windows to snapshot");
+	}
+	write_new_layout(&layout_file, layout.serialize(format)?)
+}
+
+fn layout_name_arg() -> Arg {
+	Arg::new("name")
+		.help("Name of the layout, or path to the layout file")
+		.required(true)
+}
+
+fn new_layout_name_arg() -> Arg {
+	Arg::new("new_name")
+		.help("New name of the layout, or path to the new layout file")
+		.long_help(
+			"New name of the layout, created next to the layout, or path to the new layout \
+			file, converted if its extension is of another format.",
+		)
+		.required(true)
 }
 
 struct App {
@@ -480,6 +1507,18 @@ impl App {
 						.help("Set the logging verbosity level.")
 						.long_help("Choices: [off, error, warn, info, debug, trace]"),
 				)
+				.arg(
+					Arg::new("layout_path")
+						.long("layout-path")
+						.value_name("DIRS")
+						.env("WLM_LAYOUT_PATH")
+						.global(true)
+						.help("Set the directories to search for layouts.")
+						.long_help(
+							"Set the directories to search for layouts, separated like PATH. \
+							The first directory containing a layout wins.",
+						),
+				)
 				.infer_subcommands(true)
 				.arg_required_else_help(true)
 				.subcommand(
@@ -498,6 +1537,107 @@ impl App {
 								.default_value("table")
 								.value_parser(value_parser!(Format))
 								.required(false),
+						)
+						.arg(
+							Arg::new("template")
+								.help("Output each window as a line like '{title}\\t{x},{y}'")
+								.long_help(format!(
+									"Output each window as a line where each {{field}} is replaced \
+									with that field of the window, one of {:?}. Write {{{{ and }}}} \
+									for braces, and \\t and \\n for a tab and a line break.",
+									wlm::listing::LISTING_FIELDS
+								))
+								.long("template")
+								.value_name("TEMPLATE")
+								.conflicts_with("format")
+								.required(false),
+						)
+						.arg(
+							Arg::new("title")
+								.help("Only list windows whose title matches a regular expression")
+								.short('t')
+								.long("title")
+								.value_name("REGEX"),
+						)
+						.arg(
+							Arg::new("process")
+								.help("Only list windows whose process matches a regular expression")
+								.short('p')
+								.long("process")
+								.value_name("REGEX"),
+						)
+						.arg(
+							Arg::new("screen")
+								.help("Only list windows on a screen, by id, connector name or EDID")
+								.long_help(
+									"Only list windows on a screen, given by its id, a glob its \
+									connector name matches, e.g. 'DP-*', or its EDID fingerprint.",
+								)
+								.long("screen")
+								.value_name("SCREEN"),
+						)
+						.arg(
+							Arg::new("desktop")
+								.help("Only list windows on a virtual desktop, by index or name")
+								.long("desktop")
+								.value_name("DESKTOP"),
+						)
+						.arg(
+							Arg::new("sort")
+								.help("Sort the windows of each screen")
+								.long_help(format!(
+									"Sort the windows of each screen by one of {:?}",
+									wlm::listing::SORT_KEYS
+								))
+								.long("sort")
+								.value_name("KEY")
+								.value_parser(value_parser!(SortKey)),
+						)
+						.arg(
+							Arg::new("columns")
+								.help("Columns of the table, e.g. 'title:40,process,pid'")
+								.long_help(format!(
+									"Columns of the table, separated by commas, each one of {:?} \
+									optionally followed by the most characters it takes, e.g. \
+									'title:40,process,pid'. Defaults to '{}'.",
+									wlm::listing::LISTING_FIELDS,
+									DEFAULT_COLUMNS
+								))
+								.short('c')
+								.long("columns")
+								.value_name("COLUMNS")
+								.conflicts_with("template"),
+						)
+						.arg(
+							Arg::new("width")
+								.help("Fit the table into this many characters, 0 for no limit")
+								.long_help(
+									"Fit the table into this many characters by shrinking its \
+									widest columns, or 0 for no limit. Defaults to the width of \
+									the terminal, or no limit when the output is not a terminal.",
+								)
+								.short('w')
+								.long("width")
+								.value_name("CHARS")
+								.value_parser(value_parser!(usize)),
+						),
+				)
+				.subcommand(
+					clap::Command::new("monitors")
+						.about("List monitors and their geometry, scale and rotation")
+						.arg(
+							Arg::new("format")
+								.help("Output as specified format")
+								.long_help(format!(
+									"Output as specified format {:?}",
+									&wlm::layout::FORMAT_NAMES[..5]
+								))
+								.short('f')
+								.long("format")
+								.value_name("FORMAT")
+								.default_value("table")
+								.value_parser(value_parser!(Format))
+								.required(false),
 						),
 				)
 				.subcommand(
@@ -505,9 +1645,443 @@ impl App {
 						.about("Moves windows around determined by specified layout")
 						.arg(
 							Arg::new("layout")
-								.help("Path the layout file")
+								.help("Path to a layout file, name of a layout, or - for stdin")
+								.long_help(
+									"Path to a layout file, name of a layout in the layout search \
+									path, or - to read the layout from stdin. Defaults to the \
+									layout named by WLM_LAYOUT_NAME, then to the 'default' layout.",
+								)
+								.env("WLM_LAYOUT_NAME")
+								.required(false),
+						)
+						.arg(
+							Arg::new("format")
+								.help("Format of the layout read from stdin")
+								.short('f')
+								.long("format")
+								.value_name("FORMAT")
+								.default_value("yaml")
+								.value_parser(value_parser!(Format))
+								.required(false),
+						)
+						.arg(
+							Arg::new("auto")
+								.help("Pick the layout fitting the connected displays best")
+								.long_help(
+									"Pick the layout whose match_displays fit the connected \
+									displays best, falling back to the 'default' layout. Takes \
+									precedence over the layout argument.",
+								)
+								.long("auto")
+								.action(ArgAction::SetTrue),
+						)
+						.arg(var_arg())
+						.arg(
+							Arg::new("focus")
+								.help("Raise and activate the window matched by the named rule")
+								.long("focus")
+								.value_name("RULE")
+								.required(false),
+						),
+				)
+				.subcommand(
+					clap::Command::new("undo")
+						.about("Moves the windows moved by the latest layout back")
+						.long_about(format!(
+							"Moves the windows moved by the latest applied layout back to where \
+							they were. Repeat to undo the layouts applied before it, up to the \
+							latest {}.",
+							wlm::journal::HISTORY_LIMIT
+						)),
+				)
+				.subcommand(
+					clap::Command::new("redo").about("Applies the latest undone layout again"),
+				)
+				.subcommand(
+					clap::Command::new("init")
+						.about("Creates a layout to start from")
+						.arg(
+							Arg::new("name")
+								.help("Name of the layout, or path to the layout file, to create")
+								.long_help(
+									"Name of the layout to create in the first directory of the \
+									layout search path, or path to the layout file to create. \
+									Defaults to the 'default' layout.",
+								)
+								.required(false),
+						)
+						.arg(
+							Arg::new("format")
+								.help("Format of the layout created by name")
+								.short('f')
+								.long("format")
+								.value_name("FORMAT")
+								.default_value("yaml")
+								.value_parser(value_parser!(Format))
+								.required(false),
+						)
+						.arg(
+							Arg::new("current")
+								.help("Start from the current windows instead of an example")
+								.long("current")
+								.action(ArgAction::SetTrue),
+						)
+						.arg(
+							Arg::new("force")
+								.help("Overwrite an existing layout")
+								.long("force")
+								.action(ArgAction::SetTrue),
+						),
+				)
+				.subcommand(
+					clap::Command::new("layouts")
+						.about("Manages the layouts in the layout search path")
+						.subcommand_required(true)
+						.subcommand(
+							clap::Command::new("list")
+								.about("Lists the layouts and whether they fit the connected displays"),
+						)
+						.subcommand(
+							clap::Command::new("show")
+								.about("Prints a layout file")
+								.arg(layout_name_arg()),
+						)
+						.subcommand(
+							clap::Command::new("edit")
+								.about("Opens a layout in $EDITOR and checks it once saved")
+								.arg(layout_name_arg()),
+						)
+						.subcommand(
+							clap::Command::new("cp")
+								.about("Copies a layout")
+								.arg(layout_name_arg())
+								.arg(new_layout_name_arg())
+								.arg(
+									Arg::new("force")
+										.help("Overwrite an existing layout")
+										.long("force")
+										.action(ArgAction::SetTrue),
+								),
+						)
+						.subcommand(
+							clap::Command::new("mv")
+								.about("Renames a layout")
+								.arg(layout_name_arg())
+								.arg(new_layout_name_arg())
+								.arg(
+									Arg::new("force")
+										.help("Overwrite an existing layout")
+										.long("force")
+										.action(ArgAction::SetTrue),
+								),
+						)
+						.subcommand(
+							clap::Command::new("rm")
+								.about("Removes a layout")
+								.arg(layout_name_arg()),
+						),
+				)
+				.subcommand(
+					clap::Command::new("diff")
+						.about("Shows how the windows on screen differ from a layout")
+						.long_about(
+							"Shows how the windows on screen differ from a layout: where each rule \
+							moves its window and where the window is, the rules matching no window \
+							and the windows no rule applies to. Given two layouts, shows how they \
+							differ instead. Exits with 1 if anything differs.",
+						)
+						.arg(
+							Arg::new("layout")
+								.help("Path to a layout file, name of a layout, or - for stdin")
+								.env("WLM_LAYOUT_NAME")
+								.required(false),
+						)
+						.arg(
+							Arg::new("other")
+								.help("Path to or name of a layout to compare the layout with")
+								.required(false),
+						)
+						.arg(
+							Arg::new("format")
+								.help("Output as specified format")
+								.long_help(format!(
+									"Output as specified format {:?}",
+									wlm::layout::FORMAT_NAMES
+								))
+								.short('f')
+								.long("format")
+								.value_name("FORMAT")
+								.default_value("table")
+								.value_parser(value_parser!(Format))
+								.required(false),
+						)
+						.arg(var_arg()),
+				)
+				.subcommand(
+					clap::Command::new("export")
+						.about("Writes a layout as rules of another window manager or tool")
+						.long_about(format!(
+							"Writes a layout as rules of another window manager or tool, with \
+							lengths in pixels of the connected monitors: i3 or sway for_window \
+							commands, Hyprland windowrulev2 lines, a KWin rules file, Openbox \
+							<application> blocks or a shell script using xdotool and wmctrl. \
+							Processes are matched by window class. What the target cannot express \
+							is left out, with a comment saying so. Targets: {:?}",
+							wlm::export::EXPORT_TARGETS
+						))
+						.arg(
+							Arg::new("to")
+								.help("Window manager or tool to write rules for")
+								.long("to")
+								.value_name("TARGET")
+								.value_parser(value_parser!(Target))
+								.required(true),
+						)
+						.arg(
+							Arg::new("layout")
+								.help("Path to a layout file, name of a layout, or - for stdin")
+								.env("WLM_LAYOUT_NAME")
+								.required(false),
+						)
+						.arg(var_arg()),
+				)
+				.subcommand(
+					clap::Command::new("snapshot")
+						.about("Saves a layout moving the current windows back to where they are")
+						.long_about(
+							"Saves a layout moving the current windows back to where they are. \
+							Titles are matched exactly, processes by the name of their executable, \
+							and windows without a title or too small to be real windows are left \
+							out.",
+						)
+						.arg(
+							Arg::new("name")
+								.help("Name of the layout, or path to the layout file, to create")
+								.required(true),
+						)
+						.arg(
+							Arg::new("format")
+								.help("Format of the layout created by name")
+								.short('f')
+								.long("format")
+								.value_name("FORMAT")
+								.default_value("yaml")
+								.value_parser(value_parser!(Format))
+								.required(false),
+						)
+						.arg(
+							Arg::new("relative")
+								.help("Write positions and sizes in percent of the monitor")
+								.long("relative")
+								.action(ArgAction::SetTrue),
+						)
+						.arg(
+							Arg::new("force")
+								.help("Overwrite an existing layout")
+								.long("force")
+								.action(ArgAction::SetTrue),
+						),
+				)
+				.subcommand(
+					clap::Command::new("config")
+						.about("Views or modifies a setting of a layout")
+						.long_about(
+							"Views or modifies a setting of a layout, addressed by a dotted path \
+							such as screens.1.windows.0.w. Prints the setting if no value is \
+							given, or the whole layout if no path is given.",
+						)
+						.arg(
+							Arg::new("layout")
+								.help("Path to a layout file, or name of a layout")
+								.required(true),
+						)
+						.arg(
+							Arg::new("path")
+								.help("Dotted path of the setting, e.g. screens.1.windows.0.w")
+								.required(false),
+						)
+						.arg(
+							Arg::new("value")
+								.help("Value to set, as text, or a number, boolean or YAML list where the setting takes one")
+								.allow_hyphen_values(true)
+								.required(false),
+						)
+						.arg(
+							Arg::new("unset")
+								.help("Remove the setting, e.g. a field of a rule or a whole rule")
+								.long("unset")
+								.visible_alias("reset")
+								.requires("path")
+								.conflicts_with("value")
+								.action(ArgAction::SetTrue),
+						),
+				)
+				.subcommand(
+					clap::Command::new("add")
+						.about("Adds a rule to a layout for a window on screen")
+						.long_about(
+							"Adds a rule to a layout for a window on screen, found by --title and \
+							--process or picked from a list of those found with --pick, placing it \
+							where the window currently is on the screen it is on.",
+						)
+						.arg(
+							Arg::new("layout")
+								.help("Path to a layout file, or name of a layout")
+								.required(true),
+						)
+						.arg(
+							Arg::new("pick")
+								.help("Pick the window from a list of the windows found")
+								.long("pick")
+								.action(ArgAction::SetTrue),
+						)
+						.arg(
+							Arg::new("title")
+								.help("Regular expression matching the title of the window")
+								.short('t')
+								.long("title")
+								.value_name("REGEX"),
+						)
+						.arg(
+							Arg::new("process")
+								.help("Regular expression matching the process of the window")
+								.short('p')
+								.long("process")
+								.value_name("REGEX"),
+						)
+						.group(
+							ArgGroup::new("window")
+								.args(["pick", "title", "process"])
+								.multiple(true)
+								.required(true),
+						)
+						.arg(
+							Arg::new("name")
+								.help("Name of the rule, e.g. to focus it with layout --focus")
+								.short('n')
+								.long("name")
+								.value_name("NAME"),
+						)
+						.arg(
+							Arg::new("relative")
+								.help("Write the position and size in percent of the monitor")
+								.long("relative")
+								.action(ArgAction::SetTrue),
+						),
+				)
+				.subcommand(
+					clap::Command::new("migrate")
+						.about("Converts a layout, e.g. a wlm 0.3 config, to the current version")
+						.arg(
+							Arg::new("file")
+								.help("Path to the layout file to migrate")
+								.required(true),
+						)
+						.arg(
+							Arg::new("output")
+								.help("Write the layout to OUTPUT instead of printing it")
+								.short('o')
+								.long("output")
+								.value_name("OUTPUT")
+								.required(false),
+						)
+						.arg(
+							Arg::new("format")
+								.help("Format to write the layout as, by default that of its file")
+								.short('f')
+								.long("format")
+								.value_name("FORMAT")
+								.value_parser(value_parser!(Format))
 								.required(false),
 						),
+				)
+				.subcommand(
+					clap::Command::new("import")
+						.about("Builds a layout from the setup of another tool")
+						.long_about(format!(
+							"Builds a layout from the setup of another tool: the zones of a \
+							FancyZones custom-layouts.json, as rules to fill in, or the windows \
+							of an i3 tree (i3-msg -t get_tree) or of wmctrl -lGpx, as rules \
+							moving them back to where they are. Tools: {:?}",
+							wlm::import::IMPORT_TOOLS
+						))
+						.arg(
+							Arg::new("from")
+								.help("Tool the file was written by")
+								.long("from")
+								.value_name("TOOL")
+								.value_parser(value_parser!(Tool))
+								.required(true),
+						)
+						.arg(
+							Arg::new("file")
+								.help("Path to the file to import, or - for stdin")
+								.required(true),
+						)
+						.arg(
+							Arg::new("zones")
+								.help("Name of the FancyZones layout to import, by default the first")
+								.long("zones")
+								.value_name("NAME")
+								.required(false),
+						)
+						.arg(
+							Arg::new("output")
+								.help("Write the layout to OUTPUT, a layout name or path, instead of printing it")
+								.short('o')
+								.long("output")
+								.value_name("OUTPUT")
+								.required(false),
+						)
+						.arg(
+							Arg::new("format")
+								.help("Format to write the layout as, by default that of OUTPUT or YAML")
+								.short('f')
+								.long("format")
+								.value_name("FORMAT")
+								.value_parser(value_parser!(Format))
+								.required(false),
+						)
+						.arg(
+							Arg::new("force")
+								.help("Overwrite an existing layout")
+								.long("force")
+								.action(ArgAction::SetTrue),
+						),
+				)
+				.subcommand(
+					clap::Command::new("schema")
+						.about("Prints the JSON Schema of layout files")
+						.long_about(
+							"Prints the JSON Schema of layout files, e.g. for the YAML language \
+							server to complete and check layouts in editors.",
+						),
+				)
+				.subcommand(
+					clap::Command::new("validate")
+						.about("Checks a layout for mistakes without moving any windows")
+						.arg(
+							Arg::new("layout")
+								.help("Path to a layout file, name of a layout, or - for stdin")
+								.long_help(
+									"Path to a layout file, name of a layout in the layout search \
+									path, or - to read the layout from stdin. Defaults to the \
+									layout named by WLM_LAYOUT_NAME, then to the 'default' layout.",
+								)
+								.env("WLM_LAYOUT_NAME")
+								.required(false),
+						)
+						.arg(
+							Arg::new("format")
+								.help("Format of the layout read from stdin")
+								.short('f')
+								.long("format")
+								.value_name("FORMAT")
+								.default_value("yaml")
+								.value_parser(value_parser!(Format))
+								.required(false),
+						)
+						.arg(var_arg()),
 				),
 		}
 	}
@@ -533,32 +2107,190 @@ impl App {
 		}
 
 		match matches.subcommand() {
-			Some(("ls", sub_m)) => ls(sub_m),
-			Some(("layout", sub_m)) => layout(sub_m),
+			Some(("ls", sub_m)) => ls(sub_m)?,
+			Some(("monitors", sub_m)) => monitors(sub_m)?,
+			Some(("layout", sub_m)) => layout(sub_m)?,
+			Some(("validate", sub_m)) => validate(sub_m)?,
+			Some(("init", sub_m)) => init(sub_m)?,
+			Some(("config", sub_m)) => config(sub_m)?,
+			Some(("add", sub_m)) => add(sub_m)?,
+			Some(("snapshot", sub_m)) => snapshot_layout(sub_m)?,
+			Some(("layouts", sub_m)) => layouts(sub_m)?,
+			Some(("undo", _)) => undo()?,
+			Some(("redo", _)) => redo()?,
+			Some(("diff", sub_m)) => {
+				if diff(sub_m)? {
+					std::process::exit(1);
+				}
+			}
+			Some(("export", sub_m)) => export_layout(sub_m)?,
+			Some(("migrate", sub_m)) => migrate(sub_m)?,
+			Some(("import", sub_m)) => import_layout(sub_m)?,
+			Some(("schema", _)) => print!("{}", Layout::schema()),
 			_ => eprintln!("Invalid subcommand!"),
 		}
 		Ok(())
 	}
 
 	pub fn run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-		self.run_with_args(std::env::args().into_iter())
+		self.run_with_args(std::env::args())
 	}
 }
 
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+	App::new().run()
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use assert_fs::prelude::*;
+	use wlm::layout::ScreenBuilder;
 
 	#[ignore]
 	#[test]
 	fn test_run_with_args() {
 		assert_eq!(
 			Some(()),
-			App::new().run_with_args(&vec!["fixme.exe", "ls"]).ok()
+			App::new().run_with_args(vec!["fixme.exe", "ls"]).ok()
 		);
 	}
-}
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-	App::new().run()
+	fn run_layouts(dir: &assert_fs::TempDir, args: &[&str]) -> Result<(), String> {
+		let layout_path = dir.path().to_str().unwrap();
+		let matches = App::new().args.get_matches_from(
+			["wlm", "--layout-path", layout_path, "layouts"]
+				.iter()
+				.chain(args),
+		);
+		layouts(matches.subcommand_matches("layouts").unwrap())
+	}
+
+	const WORK: &str = "screens:\n- windows:\n  - process: code\n    w: 50%\n";
+
+	fn library_of(dir: &assert_fs::TempDir) -> Library {
+		Library::from_search_path(dir.path().to_str().unwrap())
+	}
+
+	#[test]
+	fn layouts_cp() {
+		let dir = assert_fs::TempDir::new().unwrap();
+		dir.child("work.yaml").write_str(WORK).unwrap();
+		run_layouts(&dir, &["cp", "work", "home"]).unwrap();
+		dir.child("work.yaml").assert(WORK);
+		dir.child("home.yaml").assert(WORK);
+		let home = library_of(&dir).load("home").unwrap().unwrap();
+		assert_eq!(Some("50%".to_string()), home.screens[0].windows[0].w);
+	}
+
+	#[test]
+	fn layouts_cp_converts_to_path_format() {
+		let dir = assert_fs::TempDir::new().unwrap();
+		dir.child("work.yaml").write_str(WORK).unwrap();
+		let home = dir.child("home.json").path().display().to_string();
+		run_layouts(&dir, &["cp", "work", &home]).unwrap();
+		let library = library_of(&dir);
+		assert_eq!(library.load("work").unwrap(), library.load("home").unwrap());
+	}
+
+	#[test]
+	fn layouts_cp_onto_itself() {
+		let dir = assert_fs::TempDir::new().unwrap();
+		dir.child("work.yaml").write_str(WORK).unwrap();
+		assert!(run_layouts(&dir, &["cp", "--force", "work", "work"]).is_err());
+		dir.child("work.yaml").assert(WORK);
+	}
+
+	#[test]
+	fn layouts_cp_refuses_ambiguous_name() {
+		let dir = assert_fs::TempDir::new().unwrap();
+		dir.child("work.yaml").write_str(WORK).unwrap();
+		dir.child("home.yml").write_str(WORK).unwrap();
+		assert!(run_layouts(&dir, &["cp", "--force", "work", "home"]).is_err());
+		dir.child("home.yaml").assert(predicates::path::missing());
+	}
+
+	#[test]
+	fn layouts_mv() {
+		let dir = assert_fs::TempDir::new().unwrap();
+		dir.child("work.yaml").write_str(WORK).unwrap();
+		run_layouts(&dir, &["mv", "work", "home"]).unwrap();
+		dir.child("work.yaml").assert(predicates::path::missing());
+		dir.child("home.yaml").assert(WORK);
+	}
+
+	#[test]
+	fn layouts_mv_to_other_format() {
+		let dir = assert_fs::TempDir::new().unwrap();
+		dir.child("work.yaml").write_str(WORK).unwrap();
+		let work = dir.child("work.toml").path().display().to_string();
+		run_layouts(&dir, &["mv", "work", &work]).unwrap();
+		dir.child("work.yaml").assert(predicates::path::missing());
+		let work = library_of(&dir).load("work").unwrap().unwrap();
+		assert_eq!(Some("50%".to_string()), work.screens[0].windows[0].w);
+	}
+
+	#[test]
+	fn layouts_mv_onto_itself() {
+		let dir = assert_fs::TempDir::new().unwrap();
+		dir.child("work.yaml").write_str(WORK).unwrap();
+		let layout_file = dir.child("work.yaml").path().display().to_string();
+		assert!(run_layouts(&dir, &["mv", "--force", "work", &layout_file]).is_err());
+		dir.child("work.yaml").assert(WORK);
+	}
+
+	#[test]
+	fn ls_yaml_is_a_layout() {
+		let window = WindowBuilder::default()
+			.id(Some(0x3a00007))
+			.pid(Some(4242))
+			.title(Some("main.rs - Code".to_string()))
+			.x(Some("0".to_string()))
+			.build()
+			.unwrap();
+		let screen = ScreenBuilder::default()
+			.id(0)
+			.windows(vec![window])
+			.build()
+			.unwrap();
+		let mut out = Vec::new();
+		ls_layout(&mut out, vec![screen], Format::Yaml).unwrap();
+		let listed = String::from_utf8(out).unwrap();
+		assert!(listed.contains("pid: 4242"));
+		let layout = Layout::parse(&listed, Format::Yaml).unwrap();
+		let rule = &layout.screens[0].windows[0];
+		assert_eq!(None, rule.id);
+		assert_eq!(Some("0".to_string()), rule.x);
+	}
+
+	#[test]
+	fn import_output_refuses_to_overwrite() {
+		let dir = assert_fs::TempDir::new().unwrap();
+		let wmctrl = dir.child("wmctrl.txt");
+		wmctrl
+			.write_str("0x04200007  2 4343   1280 24   640  1056 slack.Slack  host Slack\n")
+			.unwrap();
+		let layout_path = dir.path().to_str().unwrap();
+		let import = |args: &[&str]| {
+			let matches = App::new().args.get_matches_from(
+				[
+					"wlm",
+					"--layout-path",
+					layout_path,
+					"import",
+					"--from",
+					"wmctrl",
+				]
+				.iter()
+				.chain([wmctrl.path().to_str().unwrap(), "-o", "work"].iter())
+				.chain(args),
+			);
+			import_layout(matches.subcommand_matches("import").unwrap())
+		};
+		import(&[]).unwrap();
+		dir.child("work.yml")
+			.assert(predicates::str::contains("slack"));
+		assert!(import(&[]).is_err());
+		import(&["--force"]).unwrap();
+	}
 }
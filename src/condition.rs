@@ -0,0 +1,370 @@
+use crate::layout::{Rect, Screen};
+
+use regex::Regex;
+use std::cmp::Ordering;
+
+/// The machine a layout is applied on, which `When` conditions are checked against.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Context {
+	pub hostname: String,
+	/// The operating system, as in `std::env::consts::OS`, e.g. `linux` or `windows`.
+	pub platform: String,
+	/// The connected screens, whose bounds are the resolutions of the monitors.
+	pub screens: Vec<Screen>,
+}
+
+impl Context {
+	pub fn new<S: Into<String>, P: Into<String>>(
+		hostname: S,
+		platform: P,
+		screens: Vec<Screen>,
+	) -> Self {
+		Context {
+			hostname: hostname.into(),
+			platform: platform.into(),
+			screens,
+		}
+	}
+
+	/// Returns the context of this machine given its connected `screens`.
+	pub fn current(screens: Vec<Screen>) -> Self {
+		Context::new(
+			gethostname::gethostname().to_string_lossy(),
+			std::env::consts::OS,
+			screens,
+		)
+	}
+
+	/// Returns the bounds of the monitor of the screen `id`, or of every monitor if `id` is not
+	/// given or not connected.
+	fn monitors(&self, id: Option<u8>) -> Vec<Rect> {
+		let screen = id.and_then(|id| self.screens.iter().find(|s| s.id == Some(id)));
+		match screen {
+			Some(screen) => screen.bounds.into_iter().collect(),
+			None => self.screens.iter().filter_map(|s| s.bounds).collect(),
+		}
+	}
+}
+
+/// A condition a screen or rule of a layout is only applied under.
+///
+/// Every specified condition must hold.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Builder, JsonSchema)]
+#[builder(setter(into))]
+#[serde(deny_unknown_fields)]
+pub struct When {
+	/// The number of connected monitors, optionally compared, e.g. `3` or `'>=2'`.
+	#[builder(default)]
+	#[serde(
+		default,
+		deserialize_with = "crate::layout::string_or_number",
+		skip_serializing_if = "Option::is_none"
+	)]
+	#[schemars(schema_with = "count_schema")]
+	pub monitors: Option<String>,
+
+	/// A glob the hostname must match, where `*` matches any characters and `?` any character,
+	/// e.g. `'work-*'`.
+	#[builder(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub hostname: Option<String>,
+
+	/// The operating system, e.g. `linux` or `windows`.
+	#[builder(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub platform: Option<String>,
+
+	/// The resolution of a monitor, optionally compared, e.g. `'>=2560x1440'`. Within a screen
+	/// with an `id`, the resolution of its monitor is compared, otherwise any monitor will do.
+	#[builder(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub resolution: Option<String>,
+}
+
+impl When {
+	/// Returns whether every condition holds in `context`, for a screen or rule applied to the
+	/// screen `id`.
+	///
+	/// # Errors
+	///
+	/// Returns an error if a condition is not valid, even if another one does not hold, so an
+	/// invalid condition fails on every machine.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use wlm::condition::{Context, WhenBuilder};
+	/// # use wlm::layout::{Rect, ScreenBuilder};
+	/// let screen = ScreenBuilder::default().id(0).bounds(Rect::new(0, 0, 2560, 1440)).build().unwrap();
+	/// let context = Context::new("work-42", "linux", vec![screen]);
+	/// let when = WhenBuilder::default()
+	///     .hostname(Some("work-*".to_string()))
+	///     .resolution(Some(">=2560x1440".to_string()))
+	///     .build()
+	///     .unwrap();
+	/// assert_eq!(Ok(true), when.matches(&context, Some(0)));
+	/// let when = WhenBuilder::default().monitors(Some("3".to_string())).build().unwrap();
+	/// assert_eq!(Ok(false), when.matches(&context, Some(0)));
+	/// ```
+	pub fn matches(&self, context: &Context, id: Option<u8>) -> Result<bool, String> {
+		self.check()?;
+		if let Some(monitors) = &self.monitors {
+			let (comparison, count) = parse_monitors(monitors)?;
+			if !comparison.holds(&[context.screens.len()], &count) {
+				return Ok(false);
+			}
+		}
+		if let Some(hostname) = &self.hostname {
+			if !glob(hostname).is_match(&context.hostname) {
+				return Ok(false);
+			}
+		}
+		if let Some(platform) = &self.platform {
+			if !platform.eq_ignore_ascii_case(&context.platform) {
+				return Ok(false);
+			}
+		}
+		if let Some(resolution) = &self.resolution {
+			let (comparison, size) = parse_resolution(resolution)?;
+			let matches = context
+				.monitors(id)
+				.iter()
+				.any(|m| comparison.holds(&[m.w as usize, m.h as usize], &size));
+			if !matches {
+				return Ok(false);
+			}
+		}
+		Ok(true)
+	}
+
+	/// Returns an error if a condition is not valid.
+	pub fn check(&self) -> Result<(), String> {
+		match self.errors().into_iter().next() {
+			Some((_, e)) => Err(e),
+			None => Ok(()),
+		}
+	}
+
+	/// Returns the field and error of each condition that is not valid, whatever the context.
+	pub fn errors(&self) -> Vec<(&'static str, String)> {
+		let mut errors = Vec::new();
+		if let Some(Err(e)) = self.monitors.as_deref().map(parse_monitors) {
+			errors.push(("monitors", e));
+		}
+		if let Some(Err(e)) = self.resolution.as_deref().map(parse_resolution) {
+			errors.push(("resolution", e));
+		}
+		errors
+	}
+}
+
+/// Parses a monitor count optionally prefixed by a comparison operator, e.g. `>=2`.
+fn parse_monitors(monitors: &str) -> Result<(Comparison, Vec<usize>), String> {
+	Comparison::parse(monitors, r"(\d+)")
+		.ok_or_else(|| format!("Invalid monitor count '{}'", monitors))
+}
+
+/// Parses a resolution optionally prefixed by a comparison operator, e.g. `>=2560x1440`.
+fn parse_resolution(resolution: &str) -> Result<(Comparison, Vec<usize>), String> {
+	Comparison::parse(resolution, r"(\d+)\s*x\s*(\d+)")
+		.ok_or_else(|| format!("Invalid resolution '{}'", resolution))
+}
+
+/// A comparison of sizes, holding along every dimension of the size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+	Equal,
+	Less,
+	LessOrEqual,
+	Greater,
+	GreaterOrEqual,
+}
+
+impl Comparison {
+	/// Parses a size optionally prefixed by a comparison operator, e.g. `>=2560x1440`, where
+	/// `size` captures each dimension of the size.
+	fn parse(comparison: &str, size: &str) -> Option<(Comparison, Vec<usize>)> {
+		let pattern = Regex::new(&format!(r"^\s*(>=|<=|>|<|=)?\s*{}\s*$", size)).unwrap();
+		let captures = pattern.captures(comparison)?;
+		let operator = match captures.get(1).map(|m| m.as_str()) {
+			Some(">=") => Comparison::GreaterOrEqual,
+			Some("<=") => Comparison::LessOrEqual,
+			Some(">") => Comparison::Greater,
+			Some("<") => Comparison::Less,
+			_ => Comparison::Equal,
+		};
+		let size = captures
+			.iter()
+			.skip(2)
+			.map(|m| m.and_then(|m| m.as_str().parse().ok()))
+			.collect::<Option<Vec<usize>>>()?;
+		Some((operator, size))
+	}
+
+	/// Returns whether `size` compares to `other` this way. A size is greater than another if it
+	/// is at least as large in every dimension and larger in one.
+	fn holds(&self, size: &[usize], other: &[usize]) -> bool {
+		let orderings = size
+			.iter()
+			.zip(other)
+			.map(|(a, b)| a.cmp(b))
+			.collect::<Vec<Ordering>>();
+		let all = |f: fn(Ordering) -> bool| orderings.iter().all(|o| f(*o));
+		match self {
+			Comparison::Equal => all(Ordering::is_eq),
+			Comparison::LessOrEqual => all(Ordering::is_le),
+			Comparison::GreaterOrEqual => all(Ordering::is_ge),
+			Comparison::Less => all(Ordering::is_le) && !all(Ordering::is_eq),
+			Comparison::Greater => all(Ordering::is_ge) && !all(Ordering::is_eq),
+		}
+	}
+}
+
+/// Converts a glob, where `*` matches any characters and `?` any character, into an anchored
+/// regular expression.
+pub(crate) fn glob(glob: &str) -> Regex {
+	let pattern = glob
+		.split('*')
+		.map(|part| {
+			part.split('?')
+				.map(regex::escape)
+				.collect::<Vec<String>>()
+				.join(".")
+		})
+		.collect::<Vec<String>>()
+		.join(".*");
+	Regex::new(&format!("(?i)^{}$", pattern)).unwrap()
+}
+
+/// Describes a count written as a number or as a string such as `">=2"`.
+fn count_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+	serde_json::from_value(serde_json::json!({
+		"type": ["string", "integer", "null"],
+		"pattern": r"^\s*(>=|<=|>|<|=)?\s*\d+\s*$",
+	}))
+	.unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::layout::ScreenBuilder;
+
+	fn context() -> Context {
+		let screens = [(0, 1920, 1080), (1, 2560, 1440)]
+			.iter()
+			.map(|&(id, w, h)| {
+				ScreenBuilder::default()
+					.id(id)
+					.bounds(Rect::new(0, 0, w, h))
+					.build()
+					.unwrap()
+			})
+			.collect();
+		Context::new("Work-Laptop", "linux", screens)
+	}
+
+	fn when(monitors: &str, hostname: &str, platform: &str, resolution: &str) -> When {
+		let some = |value: &str| Some(value.to_string()).filter(|v| !v.is_empty());
+		When {
+			monitors: some(monitors),
+			hostname: some(hostname),
+			platform: some(platform),
+			resolution: some(resolution),
+		}
+	}
+
+	#[test]
+	fn monitors() {
+		assert_eq!(Ok(true), when("2", "", "", "").matches(&context(), None));
+		assert_eq!(Ok(false), when("3", "", "", "").matches(&context(), None));
+		assert_eq!(Ok(true), when(">=2", "", "", "").matches(&context(), None));
+		assert_eq!(Ok(false), when("<2", "", "", "").matches(&context(), None));
+	}
+
+	#[test]
+	fn hostname() {
+		assert_eq!(
+			Ok(true),
+			when("", "work-*", "", "").matches(&context(), None)
+		);
+		assert_eq!(
+			Ok(true),
+			when("", "Work-Lapto?", "", "").matches(&context(), None)
+		);
+		assert_eq!(
+			Ok(false),
+			when("", "home-*", "", "").matches(&context(), None)
+		);
+		assert_eq!(
+			Ok(false),
+			when("", "work", "", "").matches(&context(), None)
+		);
+	}
+
+	#[test]
+	fn platform() {
+		assert_eq!(
+			Ok(true),
+			when("", "", "Linux", "").matches(&context(), None)
+		);
+		assert_eq!(
+			Ok(false),
+			when("", "", "windows", "").matches(&context(), None)
+		);
+	}
+
+	#[test]
+	fn resolution_of_any_monitor() {
+		assert_eq!(
+			Ok(true),
+			when("", "", "", "2560x1440").matches(&context(), None)
+		);
+		assert_eq!(
+			Ok(true),
+			when("", "", "", ">1920x1080").matches(&context(), None)
+		);
+		assert_eq!(
+			Ok(false),
+			when("", "", "", ">2560x1440").matches(&context(), None)
+		);
+		assert_eq!(
+			Ok(true),
+			when("", "", "", "<=1920x1080").matches(&context(), None)
+		);
+		assert_eq!(
+			Ok(false),
+			when("", "", "", "<1920x1080").matches(&context(), None)
+		);
+	}
+
+	#[test]
+	fn resolution_of_screen_monitor() {
+		let when = when("", "", "", ">=2560x1440");
+		assert_eq!(Ok(false), when.matches(&context(), Some(0)));
+		assert_eq!(Ok(true), when.matches(&context(), Some(1)));
+	}
+
+	#[test]
+	fn invalid() {
+		assert!(when("two", "", "", "").check().is_err());
+		assert!(when("", "", "", "4k").check().is_err());
+		assert!(when(">=2", "", "", ">=2560 x 1440").check().is_ok());
+		// Conditions that cannot hold must not hide invalid ones.
+		assert!(when(">=2", "", "", "4k").check().is_err());
+		assert!(when("", "work-*", "", "4k").check().is_err());
+		assert_eq!(
+			vec!["monitors", "resolution"],
+			when("two", "", "", "4k")
+				.errors()
+				.into_iter()
+				.map(|(field, _)| field)
+				.collect::<Vec<_>>()
+		);
+	}
+
+	#[test]
+	fn invalid_fails_where_another_does_not_hold() {
+		assert!(when("3", "", "", "4k").matches(&context(), None).is_err());
+	}
+}
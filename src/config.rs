@@ -0,0 +1,293 @@
+use crate::layout::{Format, Layout};
+
+use serde_json::{Map, Value};
+
+/// A step of a dotted path into a layout, e.g. `screens.1.windows.0.w`.
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+	Key(String),
+	Index(usize),
+}
+
+/// Parses a dotted path, where `screens[1]` may also be written for `screens.1`.
+fn parse_path(path: &str) -> Result<Vec<Step>, String> {
+	let steps = path
+		.replace('[', ".")
+		.replace(']', "")
+		.split('.')
+		.map(|step| match step.parse::<usize>() {
+			Ok(index) => Ok(Step::Index(index)),
+			Err(_) if !step.is_empty() => Ok(Step::Key(step.to_string())),
+			Err(_) => Err(format!("Invalid path '{}'", path)),
+		})
+		.collect::<Result<Vec<Step>, String>>()?;
+	Ok(steps)
+}
+
+fn to_value(layout: &Layout) -> Value {
+	serde_json::to_value(layout).unwrap()
+}
+
+/// Converts the edited `value` back into a layout, with the same parser layouts are loaded with.
+fn from_value(value: &Value) -> Result<Layout, String> {
+	Layout::parse(&value.to_string(), Format::Json).map_err(|e| e.message)
+}
+
+/// Returns the setting of `layout` at the dotted `path`, e.g. `screens.0.windows.1.process`.
+///
+/// # Errors
+///
+/// Returns an error if `layout` has no setting at `path`.
+///
+/// # Examples
+///
+/// ```
+/// # use wlm::config;
+/// # use wlm::layout::{Format, Layout};
+/// let layout = Layout::parse("screens:\n- windows:\n  - process: code\n", Format::Yaml).unwrap();
+/// assert_eq!("code", config::get(&layout, "screens.0.windows.0.process").unwrap());
+/// assert!(config::get(&layout, "screens.0.windows.1").is_err());
+/// ```
+pub fn get(layout: &Layout, path: &str) -> Result<Value, String> {
+	let mut value = &to_value(layout);
+	for step in parse_path(path)? {
+		value = match (&step, value) {
+			(Step::Key(key), Value::Object(map)) => map.get(key),
+			(Step::Index(index), Value::Array(array)) => array.get(*index),
+			_ => None,
+		}
+		.ok_or_else(|| format!("No setting '{}'", path))?;
+	}
+	Ok(value.clone())
+}
+
+/// Sets the setting of `layout` at the dotted `path` to `value`. Numbers and booleans are read
+/// as such where the setting takes them, e.g. `2` for a desktop or `true`; anything else is taken
+/// as text as written, e.g. `#general | Slack` for a title, unless the setting takes no text,
+/// where it is read as YAML, e.g. `[code, vim]`.
+///
+/// Missing objects along `path` are created, and an index one past the end of a list appends
+/// to it, e.g. to add a rule.
+///
+/// # Errors
+///
+/// Returns an error if `path` does not lead to a setting, or `value` is not valid there.
+///
+/// # Examples
+///
+/// ```
+/// # use wlm::config;
+/// # use wlm::layout::{Format, Layout};
+/// let layout = Layout::parse("screens:\n- windows:\n  - process: code\n", Format::Yaml).unwrap();
+/// let layout = config::set(&layout, "screens.0.windows.0.w", "50%").unwrap();
+/// assert_eq!(Some("50%".to_string()), layout.screens[0].windows[0].w);
+/// let layout = config::set(&layout, "screens.0.windows.1.title", "Inbox").unwrap();
+/// assert_eq!(2, layout.screens[0].windows.len());
+/// assert!(config::set(&layout, "screens.0.windows.0.colour", "red").is_err());
+/// ```
+pub fn set(layout: &Layout, path: &str, value: &str) -> Result<Layout, String> {
+	let text = Value::String(value.to_string());
+	let candidates = match serde_yaml::from_str::<Value>(value) {
+		Ok(yaml @ (Value::Bool(_) | Value::Number(_))) => vec![yaml, text],
+		Ok(yaml) if yaml != text => vec![text, yaml],
+		_ => vec![text],
+	};
+	let mut error = None;
+	for candidate in candidates {
+		match from_value(&with_setting(layout, path, candidate)?) {
+			Ok(layout) => return Ok(layout),
+			Err(e) if error.is_none() => error = Some(e),
+			Err(_) => {}
+		}
+	}
+	Err(format!(
+		"Invalid value '{}' for '{}': {}",
+		value,
+		path,
+		error.unwrap()
+	))
+}
+
+/// Returns `layout` as a value, with the setting at the dotted `path` set to `new_value`, see
+/// `set`.
+fn with_setting(layout: &Layout, path: &str, new_value: Value) -> Result<Value, String> {
+	let steps = parse_path(path)?;
+	let mut root = to_value(layout);
+	let mut target = &mut root;
+	for step in &steps {
+		if target.is_null() {
+			*target = match step {
+				Step::Key(_) => Value::Object(Map::new()),
+				Step::Index(_) => Value::Array(Vec::new()),
+			};
+		}
+		target = match (step, target) {
+			(Step::Key(key), Value::Object(map)) => map.entry(key.clone()).or_insert(Value::Null),
+			(Step::Index(index), Value::Array(array)) => {
+				if *index > array.len() {
+					return Err(format!(
+						"No setting '{}', {} is past the end of a list of {}",
+						path,
+						index,
+						array.len()
+					));
+				}
+				if *index == array.len() {
+					array.push(Value::Null);
+				}
+				&mut array[*index]
+			}
+			_ => return Err(format!("No setting '{}'", path)),
+		};
+	}
+	*target = new_value;
+	Ok(root)
+}
+
+/// Removes the setting of `layout` at the dotted `path`, e.g. a field of a rule or a whole rule.
+///
+/// # Errors
+///
+/// Returns an error if `layout` has no setting at `path`, or it cannot be removed.
+///
+/// # Examples
+///
+/// ```
+/// # use wlm::config;
+/// # use wlm::layout::{Format, Layout};
+/// let layout = Layout::parse("screens:\n- windows:\n  - process: code\n    w: 50%\n", Format::Yaml).unwrap();
+/// let layout = config::unset(&layout, "screens.0.windows.0.w").unwrap();
+/// assert_eq!(None, layout.screens[0].windows[0].w);
+/// let layout = config::unset(&layout, "screens.0.windows.0").unwrap();
+/// assert!(layout.screens[0].windows.is_empty());
+/// ```
+pub fn unset(layout: &Layout, path: &str) -> Result<Layout, String> {
+	let mut steps = parse_path(path)?;
+	let last = steps
+		.pop()
+		.ok_or_else(|| format!("Invalid path '{}'", path))?;
+	let mut root = to_value(layout);
+	let mut target = &mut root;
+	for step in &steps {
+		target = match (step, target) {
+			(Step::Key(key), Value::Object(map)) => map.get_mut(key),
+			(Step::Index(index), Value::Array(array)) => array.get_mut(*index),
+			_ => None,
+		}
+		.ok_or_else(|| format!("No setting '{}'", path))?;
+	}
+	let removed = match (&last, target) {
+		(Step::Key(key), Value::Object(map)) => map.remove(key).is_some(),
+		(Step::Index(index), Value::Array(array)) if *index < array.len() => {
+			array.remove(*index);
+			true
+		}
+		_ => false,
+	};
+	if !removed {
+		return Err(format!("No setting '{}'", path));
+	}
+	from_value(&root).map_err(|e| format!("Cannot unset '{}': {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn office() -> Layout {
+		let yaml = "screens:\n- id: 0\n  windows:\n  - process: code\n- id: 1\n  windows:\n  - name: chat\n    process: slack\n    w: 30%\n";
+		Layout::parse(yaml, Format::Yaml).unwrap()
+	}
+
+	#[test]
+	fn parse_paths() {
+		assert_eq!(
+			vec![
+				Step::Key("screens".to_string()),
+				Step::Index(1),
+				Step::Key("windows".to_string()),
+				Step::Index(0)
+			],
+			parse_path("screens[1].windows.0").unwrap()
+		);
+		assert!(parse_path("screens..windows").is_err());
+	}
+
+	#[test]
+	fn get_settings() {
+		assert_eq!("30%", get(&office(), "screens.1.windows.0.w").unwrap());
+		assert_eq!(1, get(&office(), "screens[1].id").unwrap());
+		assert!(get(&office(), "screens.1.windows.0.h").is_err());
+		assert!(get(&office(), "screens.2").is_err());
+	}
+
+	#[test]
+	fn set_number() {
+		let layout = set(&office(), "screens.1.windows.0.x", "-8").unwrap();
+		assert_eq!(Some("-8".to_string()), layout.screens[1].windows[0].x);
+	}
+
+	#[test]
+	fn set_creates_objects() {
+		let layout = set(&office(), "screens.0.windows.0.when.hostname", "work-*").unwrap();
+		let when = layout.screens[0].windows[0].when.as_ref().unwrap();
+		assert_eq!(Some("work-*".to_string()), when.hostname);
+	}
+
+	#[test]
+	fn set_invalid() {
+		assert!(set(&office(), "screens.0.windows.0.maximized", "yes please").is_err());
+		assert!(set(&office(), "screens.0.windows.3.process", "vim").is_err());
+		assert!(set(&office(), "screens.0.id", "first").is_err());
+	}
+
+	#[test]
+	fn set_text_that_is_not_yaml_text() {
+		let title = |value: &str| {
+			set(&office(), "screens.0.windows.0.title", value)
+				.unwrap()
+				.screens[0]
+				.windows[0]
+				.title
+				.clone()
+		};
+		assert_eq!(
+			Some("#general | Slack".to_string()),
+			title("#general | Slack")
+		);
+		assert_eq!(Some("Inbox: 3 new".to_string()), title("Inbox: 3 new"));
+		assert_eq!(Some("Inbox #3".to_string()), title("Inbox #3"));
+		assert_eq!(Some("1234".to_string()), title("1234"));
+		assert_eq!(Some("true".to_string()), title("true"));
+		let layout = set(&office(), "vars.port", "8080").unwrap();
+		assert_eq!(Some(&"8080".to_string()), layout.vars.get("port"));
+	}
+
+	#[test]
+	fn set_yaml() {
+		let layout = set(&office(), "screens.0.windows.0.desktop", "2").unwrap();
+		assert_eq!(
+			Some(crate::layout::Desktop::Index(2)),
+			layout.screens[0].windows[0].desktop
+		);
+		let layout = set(&office(), "screens.0.windows.0.maximized", "true").unwrap();
+		assert_eq!(Some(true), layout.screens[0].windows[0].maximized);
+		let layout = set(&office(), "include", "[base, chat]").unwrap();
+		assert_eq!(vec!["base", "chat"], layout.include);
+	}
+
+	#[test]
+	fn set_text() {
+		let layout = set(&office(), "screens.0.windows.0.title", "[Draft] -").unwrap();
+		assert_eq!(
+			Some("[Draft] -".to_string()),
+			layout.screens[0].windows[0].title
+		);
+	}
+
+	#[test]
+	fn unset_missing() {
+		assert!(unset(&office(), "screens.0.windows.0.w").is_err());
+		assert!(unset(&office(), "screens.5").is_err());
+	}
+}
@@ -0,0 +1,468 @@
+use crate::layout::{Layout, Length, Rect, Screen, Window};
+use crate::snapshot::is_junk;
+
+use serde_json::{Map, Value};
+
+/// Returns how a rule is referred to: by its name, or else by what it matches.
+///
+/// # Examples
+///
+/// ```
+/// # use wlm::diff::rule_label;
+/// # use wlm::layout::WindowBuilder;
+/// let rule = WindowBuilder::default()
+///     .title(Some("Inbox".to_string()))
+///     .process(Some("thunderbird".to_string()))
+///     .build()
+///     .unwrap();
+/// assert_eq!("title 'Inbox' process 'thunderbird'", rule_label(&rule));
+/// ```
+pub fn rule_label(rule: &Window) -> String {
+	if let Some(name) = &rule.name {
+		return format!("'{}'", name);
+	}
+	let mut label = Vec::new();
+	if let Some(title) = &rule.title {
+		label.push(format!("title '{}'", title));
+	}
+	if let Some(process) = &rule.process {
+		label.push(format!("process '{}'", process));
+	}
+	match label.is_empty() {
+		true => "rule matching nothing".to_string(),
+		false => label.join(" "),
+	}
+}
+
+/// Returns the position and size of `window`, as listed by `WindowProvider::screens`.
+fn live_rect(window: &Window) -> Option<Rect> {
+	let pixels = |value: &Option<String>| value.as_deref()?.parse::<i32>().ok();
+	Some(Rect::new(
+		pixels(&window.x)?,
+		pixels(&window.y)?,
+		pixels(&window.w)?,
+		pixels(&window.h)?,
+	))
+}
+
+/// Returns where `rule` moves a window at `actual` on the monitor with the given `bounds`, as
+/// lengths are resolved when applying a layout. Whatever the rule does not specify is left as is.
+///
+/// # Errors
+///
+/// Returns an error if a length of `rule` is not valid.
+///
+/// # Examples
+///
+/// ```
+/// # use wlm::diff::target_rect;
+/// # use wlm::layout::{Rect, WindowBuilder};
+/// let rule = WindowBuilder::default()
+///     .x(Some("0".to_string()))
+///     .w(Some("50%".to_string()))
+///     .build()
+///     .unwrap();
+/// let monitor = Rect::new(0, 0, 2560, 1440);
+/// let actual = Rect::new(100, 100, 800, 600);
+/// assert_eq!(Ok(Rect::new(0, 100, 1280, 600)), target_rect(&rule, &actual, &monitor));
+/// ```
+pub fn target_rect(rule: &Window, actual: &Rect, bounds: &Rect) -> Result<Rect, String> {
+	let resolve = |value: &Option<String>, origin: i32, extent: i32, current: i32| match value {
+		Some(value) => Ok(value.parse::<Length>()?.to_position(origin, extent)),
+		None => Ok::<i32, String>(current),
+	};
+	Ok(Rect::new(
+		resolve(&rule.x, bounds.x, bounds.w, actual.x)?,
+		resolve(&rule.y, bounds.y, bounds.h, actual.y)?,
+		resolve(&rule.w, 0, bounds.w, actual.w)?.max(1),
+		resolve(&rule.h, 0, bounds.h, actual.h)?.max(1),
+	))
+}
+
+/// A rule of a layout along with the window on screen it applies to, if any.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Step {
+	pub rule: String,
+	/// The title of the window the rule matches.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub window: Option<String>,
+	/// Where the rule moves the window.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub target: Option<Rect>,
+	/// Where the window is.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub actual: Option<Rect>,
+}
+
+impl Step {
+	/// Returns whether the window is not where the rule moves it, or the rule matches no window.
+	pub fn drifted(&self) -> bool {
+		self.window.is_none() || self.target != self.actual
+	}
+}
+
+/// A window on screen that no rule of a layout applies to.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Uncovered {
+	pub window: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub process: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub actual: Option<Rect>,
+}
+
+/// How the windows on screen compare with a layout.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Plan {
+	/// Each rule of the layout, in the order they are applied.
+	pub steps: Vec<Step>,
+	/// The windows no rule applies to, leaving out junk windows, see `snapshot::is_junk`.
+	pub uncovered: Vec<Uncovered>,
+}
+
+impl Plan {
+	/// Returns whether applying the layout would change anything, or some of it cannot apply.
+	pub fn drifted(&self) -> bool {
+		self.steps.iter().any(Step::drifted)
+	}
+}
+
+/// Returns what applying `layout` does to the windows on `screens`, as listed by
+/// `WindowProvider::screens`: each rule applies to the first window it matches, on whichever
+/// monitor it is.
+///
+/// # Errors
+///
+/// Returns an error if a length of a rule is not valid.
+pub fn plan(layout: &Layout, screens: &[Screen]) -> Result<Plan, String> {
+	let windows = screens
+		.iter()
+		.flat_map(|screen| screen.windows.iter().map(move |w| (screen, w)))
+		.collect::<Vec<(&Screen, &Window)>>();
+	let mut covered = vec![false; windows.len()];
+	let mut steps = Vec::new();
+	for rule in layout.screens.iter().flat_map(|s| &s.windows) {
+		let matched = windows.iter().position(|(_, w)| rule.matches(w));
+		let step = match matched {
+			Some(i) => {
+				covered[i] = true;
+				let (screen, window) = windows[i];
+				let actual = live_rect(window);
+				let target = match (&actual, &screen.bounds) {
+					(Some(actual), Some(bounds)) => Some(target_rect(rule, actual, bounds)?),
+					_ => None,
+				};
+				Step {
+					rule: rule_label(rule),
+					window: Some(window.title.clone().unwrap_or_default()),
+					target,
+					actual,
+				}
+			}
+			None => Step {
+				rule: rule_label(rule),
+				window: None,
+				target: None,
+				actual: None,
+			},
+		};
+		steps.push(step);
+	}
+	let uncovered = windows
+		.iter()
+		.zip(covered)
+		.filter(|((_, window), covered)| !covered && !is_junk(window))
+		.map(|((_, window), _)| Uncovered {
+			window: window.title.clone().unwrap_or_default(),
+			process: window.process.clone(),
+			actual: live_rect(window),
+		})
+		.collect();
+	Ok(Plan { steps, uncovered })
+}
+
+/// Whether a part of a layout was added, removed or changed.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+	Added,
+	Removed,
+	Changed,
+}
+
+/// A setting whose value differs between two layouts, `None` meaning it is not set.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+	pub field: String,
+	pub old: Option<Value>,
+	pub new: Option<Value>,
+}
+
+/// A difference between two layouts, in the layout itself, a screen or a rule.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Change {
+	pub kind: ChangeKind,
+	/// Where the change is, e.g. `screen 1, rule 'editor'`.
+	pub at: String,
+	/// The settings that changed, for a changed part.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub fields: Vec<FieldChange>,
+}
+
+/// Returns the settings differing between `old` and `new`, skipping `skip`, e.g. nested parts
+/// compared on their own.
+fn field_changes<T: serde::Serialize>(old: &T, new: &T, skip: &str) -> Vec<FieldChange> {
+	let object = |value: &T| match serde_json::to_value(value) {
+		Ok(Value::Object(map)) => map,
+		_ => Map::new(),
+	};
+	let (old, new) = (object(old), object(new));
+	let mut fields = old.keys().chain(new.keys()).collect::<Vec<&String>>();
+	fields.sort();
+	fields.dedup();
+	fields
+		.into_iter()
+		.filter(|field| *field != skip && old.get(*field) != new.get(*field))
+		.map(|field| FieldChange {
+			field: field.clone(),
+			old: old.get(field).cloned(),
+			new: new.get(field).cloned(),
+		})
+		.collect()
+}
+
+/// Returns how a screen is referred to: by its monitor, and its condition if it has one.
+fn screen_label(screen: &Screen) -> String {
+	let label = match screen.id {
+		Some(id) => format!("screen {}", id),
+		None => "any screen".to_string(),
+	};
+	match &screen.when {
+		Some(when) => format!("{} when {}", label, serde_json::to_string(when).unwrap()),
+		None => label,
+	}
+}
+
+/// Pairs the parts of `old` and `new` with the same key, in the order of `old` then of the
+/// parts only in `new`. Parts with the same key are paired in order.
+fn pair<'a, T, K: PartialEq>(
+	old: &'a [T],
+	new: &'a [T],
+	key: impl Fn(&T) -> K,
+) -> Vec<(Option<&'a T>, Option<&'a T>)> {
+	let mut paired = vec![false; new.len()];
+	let mut pairs = Vec::new();
+	for o in old {
+		let matched = new
+			.iter()
+			.enumerate()
+			.position(|(i, n)| !paired[i] && key(n) == key(o));
+		match matched {
+			Some(i) => {
+				paired[i] = true;
+				pairs.push((Some(o), Some(&new[i])));
+			}
+			None => pairs.push((Some(o), None)),
+		}
+	}
+	for (i, n) in new.iter().enumerate() {
+		if !paired[i] {
+			pairs.push((None, Some(n)));
+		}
+	}
+	pairs
+}
+
+/// Returns the differences between the layouts `old` and `new`.
+///
+/// Screens are compared by their monitor and condition, and rules by their name, or else by
+/// what they match, so reordering them is no change.
+///
+/// # Examples
+///
+/// ```
+/// # use wlm::diff::{compare, ChangeKind};
+/// # use wlm::layout::{Format, Layout};
+/// let old = Layout::parse("screens:\n- windows:\n  - name: editor\n    w: 50%\n", Format::Yaml).unwrap();
+/// let new = Layout::parse("screens:\n- windows:\n  - name: editor\n    w: 40%\n", Format::Yaml).unwrap();
+/// let changes = compare(&old, &new);
+/// assert_eq!(1, changes.len());
+/// assert_eq!(ChangeKind::Changed, changes[0].kind);
+/// assert_eq!("any screen, rule 'editor'", changes[0].at);
+/// assert_eq!("w", changes[0].fields[0].field);
+/// ```
+pub fn compare(old: &Layout, new: &Layout) -> Vec<Change> {
+	let mut changes = Vec::new();
+	let fields = field_changes(old, new, "screens");
+	if !fields.is_empty() {
+		changes.push(Change {
+			kind: ChangeKind::Changed,
+			at: "layout".to_string(),
+			fields,
+		});
+	}
+	let screen_key = |s: &Screen| (s.id, s.when.clone());
+	let rule_key = |w: &Window| match &w.name {
+		Some(name) => (Some(name.clone()), None, None),
+		None => (None, w.title.clone(), w.process.clone()),
+	};
+	for (old_screen, new_screen) in pair(&old.screens, &new.screens, screen_key) {
+		let (old_rules, new_rules): (&[Window], &[Window]) = match (old_screen, new_screen) {
+			(Some(o), Some(n)) => (&o.windows, &n.windows),
+			(Some(o), None) => (&o.windows, &[]),
+			(None, Some(n)) => (&[], &n.windows),
+			(None, None) => continue,
+		};
+		let screen = screen_label(old_screen.or(new_screen).unwrap());
+		if old_screen.is_none() || new_screen.is_none() {
+			changes.push(Change {
+				kind: match old_screen {
+					Some(_) => ChangeKind::Removed,
+					None => ChangeKind::Added,
+				},
+				at: screen.clone(),
+				fields: Vec::new(),
+			});
+		}
+		for (old_rule, new_rule) in pair(old_rules, new_rules, rule_key) {
+			let (kind, rule, fields) = match (old_rule, new_rule) {
+				(Some(o), Some(n)) => (ChangeKind::Changed, o, field_changes(o, n, "")),
+				(Some(o), None) => (ChangeKind::Removed, o, Vec::new()),
+				(None, Some(n)) => (ChangeKind::Added, n, Vec::new()),
+				(None, None) => continue,
+			};
+			if kind == ChangeKind::Changed && fields.is_empty() {
+				continue;
+			}
+			changes.push(Change {
+				kind,
+				at: format!("{}, rule {}", screen, rule_label(rule)),
+				fields,
+			});
+		}
+	}
+	changes
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::layout::{Format, ScreenBuilder, WindowBuilder};
+
+	fn live(title: &str, process: &str, rect: Rect) -> Window {
+		WindowBuilder::default()
+			.title(Some(title.to_string()))
+			.process(Some(process.to_string()))
+			.x(Some(rect.x.to_string()))
+			.y(Some(rect.y.to_string()))
+			.w(Some(rect.w.to_string()))
+			.h(Some(rect.h.to_string()))
+			.build()
+			.unwrap()
+	}
+
+	fn screens() -> Vec<Screen> {
+		vec![ScreenBuilder::default()
+			.id(0)
+			.bounds(Rect::new(0, 0, 2560, 1440))
+			.windows(vec![
+				live(
+					"main.rs - Code",
+					"/usr/share/code/code",
+					Rect::new(0, 0, 1280, 1440),
+				),
+				live(
+					"Slack",
+					"/usr/lib/slack/slack",
+					Rect::new(100, 100, 800, 600),
+				),
+				live(
+					"Inbox",
+					"/usr/lib/thunderbird/thunderbird",
+					Rect::new(0, 0, 960, 1080),
+				),
+			])
+			.build()
+			.unwrap()]
+	}
+
+	fn layout(yaml: &str) -> Layout {
+		Layout::parse(yaml, Format::Yaml).unwrap()
+	}
+
+	#[test]
+	fn plan_steps() {
+		let layout = layout(
+			"screens:\n- windows:\n  - name: editor\n    process: code\n    x: 0\n    y: 0\n    w: 50%\n    h: 100%\n  - name: chat\n    process: slack\n    x: 1280\n  - name: term\n    process: alacritty\n",
+		);
+		let plan = plan(&layout, &screens()).unwrap();
+		assert_eq!(3, plan.steps.len());
+		assert!(!plan.steps[0].drifted());
+		assert_eq!(Some(Rect::new(0, 0, 1280, 1440)), plan.steps[0].target);
+		assert!(plan.steps[1].drifted());
+		assert_eq!(Some(Rect::new(1280, 100, 800, 600)), plan.steps[1].target);
+		assert_eq!(Some(Rect::new(100, 100, 800, 600)), plan.steps[1].actual);
+		assert_eq!(None, plan.steps[2].window);
+		assert!(plan.drifted());
+		assert_eq!(1, plan.uncovered.len());
+		assert_eq!("Inbox", plan.uncovered[0].window);
+	}
+
+	#[test]
+	fn plan_invalid_length() {
+		let layout = layout("screens:\n- windows:\n  - process: code\n    w: wide\n");
+		assert!(plan(&layout, &screens()).is_err());
+	}
+
+	#[test]
+	fn compare_same() {
+		let yaml = "screens:\n- id: 0\n  windows:\n  - process: code\n  - process: slack\n";
+		assert!(compare(&layout(yaml), &layout(yaml)).is_empty());
+		let reordered = "screens:\n- id: 0\n  windows:\n  - process: slack\n  - process: code\n";
+		assert!(compare(&layout(yaml), &layout(reordered)).is_empty());
+	}
+
+	#[test]
+	fn compare_rules() {
+		let old = layout(
+			"screens:\n- id: 0\n  windows:\n  - process: code\n    w: 50%\n  - process: slack\n",
+		);
+		let new = layout("screens:\n- id: 0\n  windows:\n  - process: code\n    w: 40%\n  - process: alacritty\n");
+		let changes = compare(&old, &new);
+		assert_eq!(3, changes.len());
+		assert_eq!(ChangeKind::Changed, changes[0].kind);
+		assert_eq!(
+			vec![FieldChange {
+				field: "w".to_string(),
+				old: Some(Value::from("50%")),
+				new: Some(Value::from("40%")),
+			}],
+			changes[0].fields
+		);
+		assert_eq!(ChangeKind::Removed, changes[1].kind);
+		assert_eq!("screen 0, rule process 'slack'", changes[1].at);
+		assert_eq!(ChangeKind::Added, changes[2].kind);
+	}
+
+	#[test]
+	fn compare_screens_and_layout() {
+		let old = layout("vars:\n  gap: '8'\nscreens:\n- id: 0\n  windows:\n  - process: code\n");
+		let new = layout("screens:\n- id: 1\n  windows:\n  - process: code\n");
+		let changes = compare(&old, &new);
+		let kinds = changes
+			.iter()
+			.map(|c| (c.kind, c.at.as_str()))
+			.collect::<Vec<(ChangeKind, &str)>>();
+		assert_eq!(
+			vec![
+				(ChangeKind::Changed, "layout"),
+				(ChangeKind::Removed, "screen 0"),
+				(ChangeKind::Removed, "screen 0, rule process 'code'"),
+				(ChangeKind::Added, "screen 1"),
+				(ChangeKind::Added, "screen 1, rule process 'code'"),
+			],
+			kinds
+		);
+		assert_eq!("vars", changes[0].fields[0].field);
+	}
+}
@@ -0,0 +1,754 @@
+use crate::diff::rule_label;
+use crate::layout::{Desktop, Layout, Length, Rect, Screen, Window};
+
+/// The names of the window managers and tools a layout can be exported to, see `Target`.
+pub static EXPORT_TARGETS: [&str; 6] =
+	["i3", "sway", "hyprland", "kwin-rules", "openbox", "xdotool"];
+
+/// A window manager or tool a layout can be exported to.
+///
+/// Titles are matched as the layout matches them. Processes are matched against the window
+/// class, ignoring case, which usually is the name of the executable, or against the Wayland app
+/// id with sway.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+	/// `for_window` commands for the i3 config.
+	I3,
+	/// `for_window` commands for the sway config.
+	Sway,
+	/// `windowrulev2` lines for the Hyprland config.
+	Hyprland,
+	/// A KWin rules file, as kept in `~/.config/kwinrulesrc`.
+	KwinRules,
+	/// `<application>` blocks for the `<applications>` section of the Openbox `rc.xml`.
+	Openbox,
+	/// A shell script moving the windows with `xdotool` and `wmctrl`.
+	Xdotool,
+}
+
+impl std::str::FromStr for Target {
+	type Err = String;
+
+	fn from_str(target: &str) -> Result<Target, Self::Err> {
+		match target {
+			"i3" => Ok(Target::I3),
+			"sway" => Ok(Target::Sway),
+			"hyprland" => Ok(Target::Hyprland),
+			"kwin-rules" => Ok(Target::KwinRules),
+			"openbox" => Ok(Target::Openbox),
+			"xdotool" => Ok(Target::Xdotool),
+			_ => Err(format!(
+				"Unknown export target '{}', expected one of {:?}",
+				target, EXPORT_TARGETS
+			)),
+		}
+	}
+}
+
+/// A rule of a layout with its lengths resolved into pixels for the connected monitors.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Placement {
+	/// Describes the rule, see `diff::rule_label`.
+	pub label: String,
+	pub title: Option<String>,
+	pub process: Option<String>,
+	/// The top left corner, in root window coordinates, unless the window is maximized.
+	pub position: Option<(i32, i32)>,
+	/// The width and height, unless the window is maximized.
+	pub size: Option<(i32, i32)>,
+	pub maximized_vertical: bool,
+	pub maximized_horizontal: bool,
+	pub minimized: bool,
+	/// The desktop the window is moved to, unless it is sticky.
+	pub desktop: Option<Desktop>,
+	pub sticky: bool,
+	pub focus: bool,
+	/// What of the rule cannot be exported, whatever the target.
+	pub notes: Vec<String>,
+}
+
+/// Resolves the rules of `layout` against the monitors on `screens`, as listed by
+/// `WindowProvider::screens`, as lengths are resolved when applying the layout: percentages of
+/// the monitor the rule's screen id refers to, or of the first monitor if it is not connected,
+/// positions from its top left corner.
+///
+/// # Errors
+///
+/// Returns an error if a length of a rule is not valid, or is a percentage while no monitor is
+/// known.
+///
+/// # Examples
+///
+/// ```
+/// # use wlm::export::resolve;
+/// # use wlm::layout::{Layout, Rect, ScreenBuilder, WindowBuilder};
+/// let rule = WindowBuilder::default()
+///     .process(Some("code".to_string()))
+///     .x(Some("50%".to_string()))
+///     .y(Some("0".to_string()))
+///     .build()
+///     .unwrap();
+/// let mut layout = Layout::new();
+/// layout.add_rule(None, rule);
+/// let monitor = ScreenBuilder::default().bounds(Rect::new(0, 0, 2560, 1440)).build().unwrap();
+/// let placements = resolve(&layout, &[monitor]).unwrap();
+/// assert_eq!(Some((1280, 0)), placements[0].position);
+/// assert_eq!(None, placements[0].size);
+/// ```
+pub fn resolve(layout: &Layout, screens: &[Screen]) -> Result<Vec<Placement>, String> {
+	let first = screens.iter().find_map(|s| s.bounds);
+	let mut placements = Vec::new();
+	for screen in &layout.screens {
+		let bounds = screens
+			.iter()
+			.find(|s| s.id.is_some() && s.id == screen.id)
+			.and_then(|s| s.bounds)
+			.or(first);
+		for rule in &screen.windows {
+			placements.push(place(rule, bounds)?);
+		}
+	}
+	Ok(placements)
+}
+
+/// Resolves `rule` against the monitor with the given `bounds`, if known.
+fn place(rule: &Window, bounds: Option<Rect>) -> Result<Placement, String> {
+	let label = rule_label(rule);
+	// The origin and extent of the monitor along an axis.
+	type Axis = fn(&Rect) -> (i32, i32);
+	let pixels = |value: &Option<String>, axis: Axis| -> Result<_, String> {
+		let length = match value {
+			Some(value) => value.parse::<Length>()?,
+			None => return Ok(None),
+		};
+		match (length, bounds) {
+			(Length::Pixels(pixels), _) => Ok(Some(pixels)),
+			(length, Some(bounds)) => {
+				let (origin, extent) = axis(&bounds);
+				Ok(Some(length.to_position(origin, extent)))
+			}
+			(_, None) => Err(format!(
+				"Cannot resolve '{}' of {} without a connected monitor",
+				value.as_deref().unwrap_or_default(),
+				label
+			)),
+		}
+	};
+	let mut notes = Vec::new();
+	let mut pair = |a: Option<i32>, b: Option<i32>, what: &str| match (a, b) {
+		(Some(a), Some(b)) => Some((a, b)),
+		(None, None) => None,
+		_ => {
+			notes.push(format!("{} needs both of its coordinates", what));
+			None
+		}
+	};
+	let position = pair(
+		pixels(&rule.x, |b| (b.x, b.w))?,
+		pixels(&rule.y, |b| (b.y, b.h))?,
+		"position",
+	);
+	let size = pair(
+		pixels(&rule.w, |b| (0, b.w))?.map(|w| w.max(1)),
+		pixels(&rule.h, |b| (0, b.h))?.map(|h| h.max(1)),
+		"size",
+	);
+	if rule.title.is_none() && rule.process.is_none() {
+		notes.push("matches no window".to_string());
+	}
+	let maximized = rule.maximized == Some(true);
+	let maximized_vertical = maximized || rule.maximized_vertical == Some(true);
+	let maximized_horizontal = maximized || rule.maximized_horizontal == Some(true);
+	// The window manager takes care of where maximized windows are.
+	let (position, size) = match maximized_vertical && maximized_horizontal {
+		true => (None, None),
+		false => (position, size),
+	};
+	let sticky = rule.sticky == Some(true);
+	Ok(Placement {
+		label,
+		title: rule.title.clone(),
+		process: rule.process.clone(),
+		position,
+		size,
+		maximized_vertical,
+		maximized_horizontal,
+		minimized: rule.minimized == Some(true),
+		desktop: rule.desktop.clone().filter(|_| !sticky),
+		sticky,
+		focus: rule.focus == Some(true),
+		notes,
+	})
+}
+
+/// Writes `placements`, as resolved by `resolve`, in the configuration format of `target`. What
+/// `target` cannot express is left out, with a comment saying so.
+///
+/// # Examples
+///
+/// ```
+/// # use wlm::export::{export, Placement, Target};
+/// let placement = Placement {
+///     process: Some("code".to_string()),
+///     position: Some((0, 0)),
+///     size: Some((1280, 1440)),
+///     ..Placement::default()
+/// };
+/// assert_eq!(
+///     "for_window [class=\"(?i)code\"] floating enable, resize set 1280 px 1440 px, \
+///      move position 0 px 0 px\n",
+///     export(&[placement], Target::I3)
+/// );
+/// ```
+pub fn export(placements: &[Placement], target: Target) -> String {
+	let mut out = String::new();
+	let matching =
+		|placement: &&Placement| placement.title.is_some() || placement.process.is_some();
+	match target {
+		Target::Xdotool => {
+			out.push_str("#!/bin/sh\n");
+			out.push_str("# Moves the windows with xdotool and wmctrl.\n");
+		}
+		Target::KwinRules => {
+			let count = placements.iter().filter(matching).count();
+			out.push_str("[General]\n");
+			out.push_str(&format!("count={}\n", count));
+			let groups = (1..=count).map(|i| i.to_string());
+			out.push_str(&format!("rules={}\n", groups.collect::<Vec<_>>().join(",")));
+		}
+		Target::Openbox => out.push_str("<applications>\n"),
+		_ => {}
+	}
+	let mut number = 0;
+	for placement in placements {
+		let mut notes = placement.notes.clone();
+		let lines = match matching(&placement) {
+			true => match target {
+				Target::I3 | Target::Sway => for_window(placement, target, &mut notes),
+				Target::Hyprland => hyprland(placement, &mut notes),
+				Target::KwinRules => {
+					number += 1;
+					kwin_rule(placement, number, &mut notes)
+				}
+				Target::Openbox => openbox(placement, &mut notes),
+				Target::Xdotool => xdotool(placement, &mut notes),
+			},
+			false => Vec::new(),
+		};
+		if matches!(target, Target::KwinRules | Target::Xdotool) {
+			out.push('\n');
+		}
+		for note in notes {
+			let note = format!("{}: {}", placement.label, note);
+			out.push_str(&match target {
+				Target::Openbox => format!("  <!-- {} -->\n", note.replace("--", "- -")),
+				_ => format!("# {}\n", note.replace('\n', " ")),
+			});
+		}
+		for line in lines {
+			out.push_str(&line);
+			out.push('\n');
+		}
+	}
+	if target == Target::Openbox {
+		out.push_str("</applications>\n");
+	}
+	out
+}
+
+/// Returns `pattern`, which matches anywhere in a text, as a pattern that must match a whole
+/// text, for window managers matching that way.
+fn full_match(pattern: &str) -> String {
+	if pattern.contains('|') {
+		return format!(".*(?:{}).*", pattern);
+	}
+	let (start, rest) = match pattern.strip_prefix('^') {
+		Some(rest) => ("", rest),
+		None => (".*", pattern),
+	};
+	let escaped = |rest: &str| rest.chars().rev().take_while(|c| *c == '\\').count() % 2 == 1;
+	let (rest, end) = match rest.strip_suffix('$') {
+		Some(stripped) if !escaped(stripped) => (stripped, ""),
+		_ => (rest, ".*"),
+	};
+	format!("{}{}{}", start, rest, end)
+}
+
+/// Returns `pattern` as a shell-like glob, where `*` matches any text and `?` any character, if
+/// it is no more than literal text, `.` and `.*`, optionally anchored.
+fn glob(pattern: &str) -> Option<String> {
+	let (anchored_start, rest) = match pattern.strip_prefix('^') {
+		Some(rest) => (true, rest),
+		None => (false, pattern),
+	};
+	let mut glob = if anchored_start {
+		String::new()
+	} else {
+		"*".to_string()
+	};
+	let mut anchored_end = false;
+	let mut chars = rest.chars().peekable();
+	while let Some(c) = chars.next() {
+		match c {
+			'\\' => match chars.next() {
+				Some(c) if (c.is_ascii_punctuation() || c == ' ') && c != '*' && c != '?' => {
+					glob.push(c)
+				}
+				_ => return None,
+			},
+			'.' if chars.peek() == Some(&'*') => {
+				chars.next();
+				glob.push('*');
+			}
+			'.' => glob.push('?'),
+			'$' if chars.peek().is_none() => anchored_end = true,
+			'^' | '$' | '[' | ']' | '(' | ')' | '{' | '}' | '|' | '+' | '?' | '*' => return None,
+			c => glob.push(c),
+		}
+	}
+	if !anchored_end {
+		glob.push('*');
+	}
+	Some(glob)
+}
+
+/// Returns the i3 or sway `for_window` command for `placement`.
+fn for_window(placement: &Placement, target: Target, notes: &mut Vec<String>) -> Vec<String> {
+	let quote = |value: &str| format!("\"{}\"", value.replace('"', "\\\""));
+	let mut criteria = Vec::new();
+	if let Some(process) = &placement.process {
+		let key = match target {
+			Target::Sway => "app_id",
+			_ => "class",
+		};
+		criteria.push(format!("{}={}", key, quote(&format!("(?i){}", process))));
+	}
+	if let Some(title) = &placement.title {
+		criteria.push(format!("title={}", quote(title)));
+	}
+	let mut commands = Vec::new();
+	// Tiled windows fill their container, and only floating windows can be placed or sticky.
+	let tiled = placement.maximized_vertical && placement.maximized_horizontal;
+	if tiled {
+		commands.push("floating disable".to_string());
+	} else if placement.maximized_vertical || placement.maximized_horizontal {
+		notes.push("maximizing along one axis is not supported".to_string());
+	}
+	if tiled && placement.sticky {
+		notes.push("maximized windows cannot be sticky".to_string());
+	} else if placement.position.is_some() || placement.size.is_some() || placement.sticky {
+		commands.push("floating enable".to_string());
+	}
+	if let Some((w, h)) = placement.size {
+		commands.push(format!("resize set {} px {} px", w, h));
+	}
+	if let Some((x, y)) = placement.position {
+		commands.push(format!("move position {} px {} px", x, y));
+	}
+	match &placement.desktop {
+		Some(Desktop::Index(index)) => {
+			commands.push(format!("move container to workspace number {}", index + 1))
+		}
+		Some(Desktop::Name(name)) => {
+			commands.push(format!("move container to workspace {}", quote(name)))
+		}
+		None => {}
+	}
+	if placement.sticky && !tiled {
+		commands.push("sticky enable".to_string());
+	}
+	if placement.minimized {
+		commands.push("move scratchpad".to_string());
+	}
+	if placement.focus {
+		commands.push("focus".to_string());
+	}
+	if commands.is_empty() {
+		return Vec::new();
+	}
+	vec![format!(
+		"for_window [{}] {}",
+		criteria.join(" "),
+		commands.join(", ")
+	)]
+}
+
+/// Returns the Hyprland `windowrulev2` lines for `placement`.
+fn hyprland(placement: &Placement, notes: &mut Vec<String>) -> Vec<String> {
+	let mut fields = Vec::new();
+	if let Some(process) = &placement.process {
+		fields.push(format!("class:(?i){}", full_match(process)));
+	}
+	if let Some(title) = &placement.title {
+		fields.push(format!("title:{}", full_match(title)));
+	}
+	if fields.iter().any(|field| field.contains(',')) {
+		notes.push("patterns with a comma are not supported".to_string());
+		return Vec::new();
+	}
+	let fields = fields.join(", ").replace('#', "##");
+	let mut rules = Vec::new();
+	if placement.maximized_vertical && placement.maximized_horizontal {
+		rules.push("maximize".to_string());
+	} else if placement.maximized_vertical || placement.maximized_horizontal {
+		notes.push("maximizing along one axis is not supported".to_string());
+	}
+	if placement.position.is_some() || placement.size.is_some() || placement.sticky {
+		rules.push("float".to_string());
+	}
+	if let Some((x, y)) = placement.position {
+		rules.push(format!("move {} {}", x, y));
+	}
+	if let Some((w, h)) = placement.size {
+		rules.push(format!("size {} {}", w, h));
+	}
+	match &placement.desktop {
+		Some(Desktop::Index(index)) => rules.push(format!("workspace {}", index + 1)),
+		Some(Desktop::Name(name)) => rules.push(format!("workspace name:{}", name)),
+		None => {}
+	}
+	if placement.sticky {
+		rules.push("pin".to_string());
+	}
+	if placement.minimized {
+		rules.push("workspace special silent".to_string());
+	}
+	if placement.focus {
+		notes.push("focusing is not supported".to_string());
+	}
+	rules
+		.into_iter()
+		.map(|rule| format!("windowrulev2 = {}, {}", rule, fields))
+		.collect()
+}
+
+/// Returns the group of a KWin rules file for `placement`, the `number`th rule of the file. Rules
+/// apply initially, so windows can be moved afterwards.
+fn kwin_rule(placement: &Placement, number: usize, notes: &mut Vec<String>) -> Vec<String> {
+	// KConfig escapes backslashes in values.
+	let value = |value: String| value.replace('\\', "\\\\");
+	let mut lines = vec![
+		format!("[{}]", number),
+		format!("Description={}", value(format!("wlm {}", placement.label))),
+	];
+	if let Some(process) = &placement.process {
+		lines.push(format!(
+			"wmclass={}",
+			value(format!("(?i){}", full_match(process)))
+		));
+		lines.push("wmclasscomplete=false".to_string());
+		lines.push("wmclassmatch=3".to_string());
+	}
+	if let Some(title) = &placement.title {
+		lines.push(format!("title={}", value(full_match(title))));
+		lines.push("titlematch=3".to_string());
+	}
+	let mut apply = |key: &str, setting: String| {
+		lines.push(format!("{}={}", key, setting));
+		lines.push(format!("{}rule=3", key));
+	};
+	if let Some((x, y)) = placement.position {
+		apply("position", format!("{},{}", x, y));
+	}
+	if let Some((w, h)) = placement.size {
+		apply("size", format!("{},{}", w, h));
+	}
+	if placement.maximized_vertical {
+		apply("maximizevert", "true".to_string());
+	}
+	if placement.maximized_horizontal {
+		apply("maximizehoriz", "true".to_string());
+	}
+	if placement.minimized {
+		apply("minimize", "true".to_string());
+	}
+	match &placement.desktop {
+		Some(Desktop::Index(index)) => apply("desktop", (index + 1).to_string()),
+		Some(Desktop::Name(_)) => notes.push("desktops can only be given by index".to_string()),
+		None => {}
+	}
+	if placement.sticky {
+		apply("onalldesktops", "true".to_string());
+	}
+	if placement.focus {
+		notes.push("focusing is not supported".to_string());
+	}
+	lines
+}
+
+/// Returns the Openbox `<application>` block for `placement`, which Openbox matches by globs
+/// rather than patterns.
+fn openbox(placement: &Placement, notes: &mut Vec<String>) -> Vec<String> {
+	let escape = |value: &str| {
+		value
+			.replace('&', "&amp;")
+			.replace('<', "&lt;")
+			.replace('>', "&gt;")
+			.replace('"', "&quot;")
+	};
+	let mut attributes = Vec::new();
+	for (attribute, pattern) in [("name", &placement.process), ("title", &placement.title)] {
+		if let Some(pattern) = pattern {
+			match glob(pattern) {
+				Some(glob) => attributes.push(format!(" {}=\"{}\"", attribute, escape(&glob))),
+				None => {
+					notes.push(format!("'{}' cannot be written as a glob", pattern));
+					return Vec::new();
+				}
+			}
+		}
+	}
+	let mut lines = vec![format!("  <application{}>", attributes.concat())];
+	if let Some((x, y)) = placement.position {
+		lines.push(format!(
+			"    <position force=\"yes\"><x>{}</x><y>{}</y></position>",
+			x, y
+		));
+	}
+	if let Some((w, h)) = placement.size {
+		lines.push(format!(
+			"    <size><width>{}</width><height>{}</height></size>",
+			w, h
+		));
+	}
+	let maximized = match (placement.maximized_vertical, placement.maximized_horizontal) {
+		(true, true) => Some("yes"),
+		(true, false) => Some("vertical"),
+		(false, true) => Some("horizontal"),
+		(false, false) => None,
+	};
+	if let Some(maximized) = maximized {
+		lines.push(format!("    <maximized>{}</maximized>", maximized));
+	}
+	if placement.minimized {
+		lines.push("    <iconic>yes</iconic>".to_string());
+	}
+	match &placement.desktop {
+		Some(Desktop::Index(index)) => lines.push(format!("    <desktop>{}</desktop>", index + 1)),
+		Some(Desktop::Name(_)) => notes.push("desktops can only be given by index".to_string()),
+		None => {}
+	}
+	if placement.sticky {
+		lines.push("    <desktop>all</desktop>".to_string());
+	}
+	if placement.focus {
+		lines.push("    <focus>yes</focus>".to_string());
+	}
+	lines.push("  </application>".to_string());
+	lines
+}
+
+/// Returns the shell commands moving the first visible window `placement` matches, if any.
+fn xdotool(placement: &Placement, notes: &mut Vec<String>) -> Vec<String> {
+	let quote = |value: &str| format!("'{}'", value.replace('\'', "'\\''"));
+	let mut search = vec!["xdotool search --limit 1 --all --onlyvisible".to_string()];
+	if let Some(process) = &placement.process {
+		search.push(format!("--class {}", quote(process)));
+	}
+	if let Some(title) = &placement.title {
+		search.push(format!("--name {}", quote(title)));
+	}
+	let mut commands = Vec::new();
+	match &placement.desktop {
+		Some(Desktop::Index(index)) => commands.push(format!(
+			"xdotool set_desktop_for_window \"$window\" {}",
+			index
+		)),
+		Some(Desktop::Name(_)) => notes.push("desktops can only be given by index".to_string()),
+		None => {}
+	}
+	if placement.sticky {
+		commands.push("wmctrl -i -r \"$window\" -b add,sticky".to_string());
+	}
+	if let Some((x, y)) = placement.position {
+		commands.push(format!("xdotool windowmove \"$window\" {} {}", x, y));
+	}
+	if let Some((w, h)) = placement.size {
+		commands.push(format!("xdotool windowsize \"$window\" {} {}", w, h));
+	}
+	let maximized = match (placement.maximized_vertical, placement.maximized_horizontal) {
+		(true, true) => Some("maximized_vert,maximized_horz"),
+		(true, false) => Some("maximized_vert"),
+		(false, true) => Some("maximized_horz"),
+		(false, false) => None,
+	};
+	if let Some(maximized) = maximized {
+		commands.push(format!("wmctrl -i -r \"$window\" -b add,{}", maximized));
+	}
+	if placement.minimized {
+		commands.push("xdotool windowminimize \"$window\"".to_string());
+	}
+	if placement.focus {
+		commands.push("xdotool windowactivate \"$window\"".to_string());
+	}
+	if commands.is_empty() {
+		return Vec::new();
+	}
+	let mut lines = vec![
+		format!("window=$({})", search.join(" ")),
+		"if [ -n \"$window\" ]; then".to_string(),
+	];
+	lines.extend(commands.into_iter().map(|command| format!("\t{}", command)));
+	lines.push("fi".to_string());
+	lines
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::layout::{Format, ScreenBuilder};
+
+	fn screens() -> Vec<Screen> {
+		let monitor = |id: u8, x: i32| {
+			ScreenBuilder::default()
+				.id(id)
+				.bounds(Rect::new(x, 0, 1920, 1080))
+				.build()
+				.unwrap()
+		};
+		vec![monitor(0, 0), monitor(1, 1920)]
+	}
+
+	fn placements() -> Vec<Placement> {
+		let layout = Layout::parse(
+			concat!(
+				"screens:\n",
+				"- id: 1\n",
+				"  windows:\n",
+				"  - {title: '^Inbox$', process: thunderbird, x: 50%, y: 0, w: 50%, h: 100%, desktop: 1}\n",
+				"  - {process: slack, maximized: true, x: 0, y: 0, sticky: true}\n",
+				"  - {name: editor, w: 50%}\n",
+			),
+			Format::Yaml,
+		)
+		.unwrap();
+		resolve(&layout, &screens()).unwrap()
+	}
+
+	#[test]
+	fn resolve_against_monitor_of_screen() {
+		let placements = placements();
+		assert_eq!(3, placements.len());
+		assert_eq!(Some((2880, 0)), placements[0].position);
+		assert_eq!(Some((960, 1080)), placements[0].size);
+		assert_eq!(Some(Desktop::Index(1)), placements[0].desktop);
+		assert!(placements[1].maximized_vertical && placements[1].maximized_horizontal);
+		assert_eq!(None, placements[2].size);
+		assert_eq!(
+			vec![
+				"size needs both of its coordinates".to_string(),
+				"matches no window".to_string()
+			],
+			placements[2].notes
+		);
+	}
+
+	#[test]
+	fn resolve_percentages_without_monitor() {
+		let mut layout = Layout::new();
+		layout.add_rule(
+			None,
+			Window {
+				w: Some("50%".to_string()),
+				..Window::new()
+			},
+		);
+		assert!(resolve(&layout, &[]).is_err());
+	}
+
+	#[test]
+	fn full_match_patterns() {
+		assert_eq!("Inbox", full_match("^Inbox$"));
+		assert_eq!(".*code.*", full_match("code"));
+		assert_eq!(".*\\$.*", full_match("\\$"));
+		assert_eq!(".*(?:^a|b).*", full_match("^a|b"));
+	}
+
+	#[test]
+	fn glob_patterns() {
+		assert_eq!(
+			Some("Inbox (3) - Thunderbird".to_string()),
+			glob(r"^Inbox \(3\) \- Thunderbird$")
+		);
+		assert_eq!(Some("*code*".to_string()), glob("code"));
+		assert_eq!(Some("main?rs*".to_string()), glob("^main.rs"));
+		assert_eq!(
+			Some("*Mozilla*Firefox".to_string()),
+			glob("Mozilla.*Firefox$")
+		);
+		assert_eq!(None, glob("(?i)code"));
+		assert_eq!(None, glob(r"\d+"));
+	}
+
+	#[test]
+	fn export_i3_and_sway() {
+		let i3 = export(&placements(), Target::I3);
+		assert_eq!(
+			"# process 'slack': maximized windows cannot be sticky\n\
+			# 'editor': size needs both of its coordinates\n\
+			# 'editor': matches no window\n",
+			i3.lines()
+				.filter(|line| line.starts_with('#'))
+				.map(|line| format!("{}\n", line))
+				.collect::<String>()
+		);
+		assert!(i3.contains(
+			"for_window [class=\"(?i)thunderbird\" title=\"^Inbox$\"] floating enable, \
+			resize set 960 px 1080 px, move position 2880 px 0 px, \
+			move container to workspace number 2\n"
+		));
+		assert!(i3.contains("for_window [class=\"(?i)slack\"] floating disable\n"));
+		let sway = export(&placements(), Target::Sway);
+		assert!(sway.contains("for_window [app_id=\"(?i)slack\"]"));
+	}
+
+	#[test]
+	fn export_hyprland() {
+		let hyprland = export(&placements(), Target::Hyprland);
+		assert!(hyprland
+			.contains("windowrulev2 = size 960 1080, class:(?i).*thunderbird.*, title:Inbox\n"));
+		assert!(hyprland
+			.contains("windowrulev2 = workspace 2, class:(?i).*thunderbird.*, title:Inbox\n"));
+		assert!(hyprland.contains("windowrulev2 = maximize, class:(?i).*slack.*\n"));
+		assert!(hyprland.contains("windowrulev2 = pin, class:(?i).*slack.*\n"));
+	}
+
+	#[test]
+	fn export_kwin_rules() {
+		let kwin = export(&placements(), Target::KwinRules);
+		assert!(kwin.starts_with("[General]\ncount=2\nrules=1,2\n\n[1]\n"));
+		assert!(kwin.contains("wmclass=(?i).*thunderbird.*\n"));
+		assert!(kwin.contains("position=2880,0\npositionrule=3\nsize=960,1080\nsizerule=3\n"));
+		assert!(kwin.contains("desktop=2\ndesktoprule=3\n"));
+		assert!(kwin.contains("[2]\n"));
+		assert!(kwin.contains("onalldesktops=true\nonalldesktopsrule=3\n"));
+	}
+
+	#[test]
+	fn export_openbox() {
+		let openbox = export(&placements(), Target::Openbox);
+		assert!(openbox.starts_with(
+			"<applications>\n  <application name=\"*thunderbird*\" title=\"Inbox\">\n    \
+			<position force=\"yes\"><x>2880</x><y>0</y></position>\n"
+		));
+		assert!(openbox.contains("    <maximized>yes</maximized>\n    <desktop>all</desktop>\n"));
+		assert!(openbox.ends_with("</applications>\n"));
+	}
+
+	#[test]
+	fn export_xdotool() {
+		let script = export(&placements(), Target::Xdotool);
+		assert!(script.starts_with("#!/bin/sh\n"));
+		assert!(script.contains(
+			"window=$(xdotool search --limit 1 --all --onlyvisible --class 'thunderbird' --name '^Inbox$')\n\
+			if [ -n \"$window\" ]; then\n\
+			\txdotool set_desktop_for_window \"$window\" 1\n\
+			\txdotool windowmove \"$window\" 2880 0\n\
+			\txdotool windowsize \"$window\" 960 1080\n\
+			fi\n"
+		));
+		assert!(
+			script.contains("\twmctrl -i -r \"$window\" -b add,maximized_vert,maximized_horz\n")
+		);
+	}
+}
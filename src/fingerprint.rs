@@ -0,0 +1,274 @@
+use crate::condition::glob;
+use crate::layout::Screen;
+
+/// A display a layout is meant for, as listed in the `match_displays` of a layout.
+///
+/// Every specified attribute must match the connected display.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Builder, JsonSchema)]
+#[builder(setter(into))]
+#[serde(deny_unknown_fields)]
+pub struct DisplayMatcher {
+	/// A glob the connector name of the display must match, e.g. `'eDP-*'`.
+	#[builder(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub name: Option<String>,
+
+	/// The EDID fingerprint of the display, identifying its model and serial number, as listed
+	/// by `wlm ls -f yaml`.
+	#[builder(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub edid: Option<String>,
+
+	/// The resolution of the display, e.g. `2560x1440`.
+	#[builder(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub resolution: Option<String>,
+}
+
+impl DisplayMatcher {
+	/// Returns how well `screen` matches, or `None` if it does not. Matching the EDID weighs the
+	/// most, then the name, then the resolution.
+	pub fn score(&self, screen: &Screen) -> Option<u32> {
+		let mut score = 0;
+		if let Some(edid) = &self.edid {
+			if !screen
+				.edid
+				.as_ref()
+				.is_some_and(|e| e.eq_ignore_ascii_case(edid))
+			{
+				return None;
+			}
+			score += 4;
+		}
+		if let Some(name) = &self.name {
+			if !screen.name.as_ref().is_some_and(|n| glob(name).is_match(n)) {
+				return None;
+			}
+			score += 2;
+		}
+		if let Some(resolution) = &self.resolution {
+			let bounds = screen.bounds?;
+			let actual = format!("{}x{}", bounds.w, bounds.h);
+			if resolution.replace(' ', "") != actual {
+				return None;
+			}
+			score += 1;
+		}
+		Some(score)
+	}
+}
+
+/// How well the `match_displays` of a layout fit the connected displays.
+///
+/// A fit covering every connected display beats any partial one, then the higher score wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Fit {
+	/// Whether each connected display is matched.
+	pub complete: bool,
+	pub score: u32,
+}
+
+/// Returns how well `matchers` fit the connected `screens`, each matcher matching a different
+/// display, or `None` if they do not.
+///
+/// # Examples
+///
+/// ```
+/// # use wlm::fingerprint::{fit, DisplayMatcherBuilder};
+/// # use wlm::layout::ScreenBuilder;
+/// let laptop = ScreenBuilder::default().name(Some("eDP-1".to_string())).build().unwrap();
+/// let builtin = DisplayMatcherBuilder::default().name(Some("eDP-*".to_string())).build().unwrap();
+/// assert!(fit(&[builtin.clone()], &[laptop.clone()]).unwrap().complete);
+/// assert_eq!(None, fit(&[builtin.clone(), builtin], &[laptop]));
+/// ```
+pub fn fit(matchers: &[DisplayMatcher], screens: &[Screen]) -> Option<Fit> {
+	if matchers.is_empty() {
+		return None;
+	}
+	let score = best_assignment(matchers, screens, &mut vec![false; screens.len()])?;
+	Some(Fit {
+		complete: matchers.len() == screens.len(),
+		score,
+	})
+}
+
+/// Returns the highest total score of matching each of `matchers` to a different screen not
+/// already `used`.
+fn best_assignment(
+	matchers: &[DisplayMatcher],
+	screens: &[Screen],
+	used: &mut [bool],
+) -> Option<u32> {
+	let Some((matcher, rest)) = matchers.split_first() else {
+		return Some(0);
+	};
+	let mut best = None;
+	for (i, screen) in screens.iter().enumerate() {
+		if used[i] {
+			continue;
+		}
+		if let Some(score) = matcher.score(screen) {
+			used[i] = true;
+			if let Some(total) = best_assignment(rest, screens, used) {
+				best = best.max(Some(score + total));
+			}
+			used[i] = false;
+		}
+	}
+	best
+}
+
+/// Returns the name of the candidate whose `match_displays` fit the connected `screens` best,
+/// the first candidate winning ties.
+pub fn best_match<I>(candidates: I, screens: &[Screen]) -> Option<String>
+where
+	I: IntoIterator<Item = (String, Vec<DisplayMatcher>)>,
+{
+	let mut best: Option<(Fit, String)> = None;
+	for (name, matchers) in candidates {
+		if let Some(fit) = fit(&matchers, screens) {
+			log::debug!("Layout '{}' fits the displays: {:?}", name, fit);
+			if best.as_ref().is_none_or(|(best, _)| fit > *best) {
+				best = Some((fit, name));
+			}
+		}
+	}
+	best.map(|(_, name)| name)
+}
+
+/// Returns the fingerprint of a display given its EDID: the manufacturer, product code and
+/// serial number, which identify a display regardless of how it is connected.
+///
+/// Returns `None` if `edid` is not an EDID.
+///
+/// # Examples
+///
+/// ```
+/// # use wlm::fingerprint::edid_fingerprint;
+/// let mut edid = vec![0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+/// edid.extend([0x10, 0xac, 0x5a, 0xa0, 0x4c, 0x30, 0x35, 0x31, 0x1c, 0x1e]);
+/// edid.resize(128, 0);
+/// assert_eq!(Some("10ac5aa04c303531".to_string()), edid_fingerprint(&edid));
+/// assert_eq!(None, edid_fingerprint(&[0x10, 0xac]));
+/// ```
+pub fn edid_fingerprint(edid: &[u8]) -> Option<String> {
+	const HEADER: [u8; 8] = [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+	if edid.len() < 128 || edid[..8] != HEADER {
+		return None;
+	}
+	Some(edid[8..16].iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::layout::{Rect, ScreenBuilder};
+
+	fn display(name: &str, edid: &str, w: i32, h: i32) -> Screen {
+		ScreenBuilder::default()
+			.name(Some(name.to_string()))
+			.edid(Some(edid.to_string()))
+			.bounds(Rect::new(0, 0, w, h))
+			.build()
+			.unwrap()
+	}
+
+	fn matcher(name: Option<&str>, edid: Option<&str>, resolution: Option<&str>) -> DisplayMatcher {
+		DisplayMatcher {
+			name: name.map(String::from),
+			edid: edid.map(String::from),
+			resolution: resolution.map(String::from),
+		}
+	}
+
+	fn laptop() -> Vec<Screen> {
+		vec![display("eDP-1", "30e4f60500000000", 1920, 1200)]
+	}
+
+	fn docked() -> Vec<Screen> {
+		vec![
+			display("eDP-1", "30e4f60500000000", 1920, 1200),
+			display("DP-1", "10ac5aa04c303531", 2560, 1440),
+		]
+	}
+
+	fn candidates() -> Vec<(String, Vec<DisplayMatcher>)> {
+		vec![
+			("unmatched".to_string(), Vec::new()),
+			(
+				"laptop".to_string(),
+				vec![matcher(Some("eDP-*"), None, None)],
+			),
+			(
+				"docked".to_string(),
+				vec![
+					matcher(Some("eDP-*"), None, None),
+					matcher(None, Some("10AC5AA04C303531"), None),
+				],
+			),
+			(
+				"any-external".to_string(),
+				vec![
+					matcher(Some("eDP-*"), None, None),
+					matcher(Some("DP-*"), None, Some("2560x1440")),
+				],
+			),
+		]
+	}
+
+	#[test]
+	fn score() {
+		let screen = &docked()[1];
+		assert_eq!(Some(0), matcher(None, None, None).score(screen));
+		assert_eq!(
+			Some(7),
+			matcher(Some("DP-?"), Some("10ac5aa04c303531"), Some("2560 x 1440")).score(screen)
+		);
+		assert_eq!(None, matcher(Some("HDMI-*"), None, None).score(screen));
+		assert_eq!(None, matcher(None, None, Some("1920x1080")).score(screen));
+	}
+
+	#[test]
+	fn best_match_undocked() {
+		assert_eq!(
+			Some("laptop".to_string()),
+			best_match(candidates(), &laptop())
+		);
+	}
+
+	#[test]
+	fn best_match_prefers_edid() {
+		assert_eq!(
+			Some("docked".to_string()),
+			best_match(candidates(), &docked())
+		);
+	}
+
+	#[test]
+	fn best_match_prefers_complete_fit() {
+		let candidates = vec![
+			(
+				"exact-laptop".to_string(),
+				vec![matcher(
+					Some("eDP-1"),
+					Some("30e4f60500000000"),
+					Some("1920x1200"),
+				)],
+			),
+			(
+				"any-two".to_string(),
+				vec![matcher(None, None, None), matcher(None, None, None)],
+			),
+		];
+		assert_eq!(
+			Some("any-two".to_string()),
+			best_match(candidates, &docked())
+		);
+	}
+
+	#[test]
+	fn best_match_none() {
+		let screens = vec![display("HDMI-1", "", 1920, 1080)];
+		assert_eq!(None, best_match(candidates(), &screens));
+	}
+}
@@ -0,0 +1,502 @@
+use crate::layout::{Desktop, Layout, Length, Rect, Screen, Window, LAYOUT_VERSION};
+use crate::snapshot::snapshot;
+
+/// The names of the tools a layout can be imported from, see `Tool`.
+pub static IMPORT_TOOLS: [&str; 3] = ["fancyzones", "i3-tree", "wmctrl"];
+
+/// A tool a layout can be imported from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tool {
+	/// The `custom-layouts.json` of PowerToys FancyZones, see `fancyzones`.
+	FancyZones,
+	/// The output of `i3-msg -t get_tree`, see `i3_tree`.
+	I3Tree,
+	/// The output of `wmctrl -lGpx`, see `wmctrl`.
+	Wmctrl,
+}
+
+impl std::str::FromStr for Tool {
+	type Err = String;
+
+	fn from_str(tool: &str) -> Result<Tool, Self::Err> {
+		match tool {
+			"fancyzones" => Ok(Tool::FancyZones),
+			"i3-tree" => Ok(Tool::I3Tree),
+			"wmctrl" => Ok(Tool::Wmctrl),
+			_ => Err(format!(
+				"Unknown import tool '{}', expected one of {:?}",
+				tool, IMPORT_TOOLS
+			)),
+		}
+	}
+}
+
+/// Builds a layout from `content`, as written by `tool`. `zones` names the FancyZones layout to
+/// import, see `fancyzones`.
+///
+/// # Errors
+///
+/// Returns an error if `content` is not what `tool` writes.
+pub fn import(content: &str, tool: Tool, zones: Option<&str>) -> Result<Layout, String> {
+	match tool {
+		Tool::FancyZones => fancyzones(content, zones),
+		Tool::I3Tree => i3_tree(content),
+		Tool::Wmctrl => wmctrl(content),
+	}
+}
+
+/// The `custom-layouts.json` of PowerToys FancyZones.
+#[derive(Debug, Deserialize)]
+struct CustomLayouts {
+	#[serde(rename = "custom-layouts")]
+	layouts: Vec<CustomLayout>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CustomLayout {
+	name: String,
+	#[serde(rename = "type")]
+	kind: String,
+	info: serde_json::Value,
+}
+
+/// Zones placed freely, in pixels of a reference monitor.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct CanvasInfo {
+	ref_width: i32,
+	ref_height: i32,
+	zones: Vec<CanvasZone>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CanvasZone {
+	#[serde(rename = "X")]
+	x: i32,
+	#[serde(rename = "Y")]
+	y: i32,
+	width: i32,
+	height: i32,
+}
+
+/// Zones made of the cells of a grid, whose rows and columns are sized in hundredths of a
+/// percent.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct GridInfo {
+	rows_percentage: Vec<u32>,
+	columns_percentage: Vec<u32>,
+	/// The zone of each cell, row by row.
+	cell_child_map: Vec<Vec<usize>>,
+}
+
+/// Returns the zones of a grid, each spanning the cells mapped to it, as `(x, y, w, h)` in
+/// hundredths of a percent.
+fn grid_zones(grid: &GridInfo) -> Result<Vec<(u32, u32, u32, u32)>, String> {
+	let offsets = |sizes: &[u32]| {
+		let mut offsets = vec![0];
+		for size in sizes {
+			offsets.push(offsets.last().unwrap() + size);
+		}
+		offsets
+	};
+	let rows = offsets(&grid.rows_percentage);
+	let columns = offsets(&grid.columns_percentage);
+	let count = grid
+		.cell_child_map
+		.iter()
+		.flatten()
+		.max()
+		.map_or(0, |max| max + 1);
+	let mut zones = Vec::new();
+	for zone in 0..count {
+		let cells = grid
+			.cell_child_map
+			.iter()
+			.enumerate()
+			.flat_map(|(row, cells)| {
+				cells
+					.iter()
+					.enumerate()
+					.map(move |(column, z)| (row, column, *z))
+			})
+			.filter(|(_, _, z)| *z == zone)
+			.collect::<Vec<_>>();
+		let (Some(top), Some(bottom), Some(left), Some(right)) = (
+			cells.iter().map(|c| c.0).min(),
+			cells.iter().map(|c| c.0).max(),
+			cells.iter().map(|c| c.1).min(),
+			cells.iter().map(|c| c.1).max(),
+		) else {
+			return Err(format!("Zone {} of the grid has no cell", zone));
+		};
+		if bottom + 1 >= rows.len() || right + 1 >= columns.len() {
+			return Err("The grid has more cells than rows or columns".to_string());
+		}
+		zones.push((
+			columns[left],
+			rows[top],
+			columns[right + 1] - columns[left],
+			rows[bottom + 1] - rows[top],
+		));
+	}
+	Ok(zones)
+}
+
+/// Builds a layout from the `custom-layouts.json` of PowerToys FancyZones, with a rule named
+/// `zone-1`, `zone-2`, ... for each zone of the FancyZones layout named `name`, or of the first
+/// one. The rules match no window, so are meant to be filled in with a title or process, and
+/// are sized in percent of the monitor.
+///
+/// # Errors
+///
+/// Returns an error if `content` is not a `custom-layouts.json`, or has no layout named `name`.
+///
+/// # Examples
+///
+/// ```
+/// # use wlm::import::fancyzones;
+/// let content = r#"{"custom-layouts": [{"name": "Focus", "type": "canvas", "info": {
+///     "ref-width": 1920, "ref-height": 1080,
+///     "zones": [{"X": 0, "Y": 0, "width": 1280, "height": 1080}]
+/// }}]}"#;
+/// let layout = fancyzones(content, None).unwrap();
+/// let zone = &layout.screens[0].windows[0];
+/// assert_eq!(Some("zone-1".to_string()), zone.name);
+/// assert_eq!(Some("66.67%".to_string()), zone.w);
+/// ```
+pub fn fancyzones(content: &str, name: Option<&str>) -> Result<Layout, String> {
+	let custom: CustomLayouts = serde_json::from_str(content)
+		.map_err(|e| format!("Failed to read FancyZones layouts: {}", e))?;
+	let names = custom
+		.layouts
+		.iter()
+		.map(|l| l.name.clone())
+		.collect::<Vec<_>>();
+	let custom = match name {
+		Some(name) => custom.layouts.into_iter().find(|l| l.name == name),
+		None => custom.layouts.into_iter().next(),
+	}
+	.ok_or_else(|| match name {
+		Some(name) => format!("No FancyZones layout named '{}' in {:?}", name, names),
+		None => "No FancyZones layouts".to_string(),
+	})?;
+	let invalid =
+		|e: serde_json::Error| format!("Invalid FancyZones layout '{}': {}", custom.name, e);
+	let zones = match custom.kind.as_str() {
+		"canvas" => {
+			let canvas: CanvasInfo =
+				serde_json::from_value(custom.info.clone()).map_err(invalid)?;
+			let (w, h) = (canvas.ref_width, canvas.ref_height);
+			canvas
+				.zones
+				.iter()
+				.map(|zone| {
+					[
+						Length::percent_of(zone.x, w),
+						Length::percent_of(zone.y, h),
+						Length::percent_of(zone.width, w),
+						Length::percent_of(zone.height, h),
+					]
+				})
+				.collect::<Vec<_>>()
+		}
+		"grid" => {
+			let grid: GridInfo = serde_json::from_value(custom.info.clone()).map_err(invalid)?;
+			let percent = |hundredths: u32| Length::Percent(f64::from(hundredths) / 100.0);
+			grid_zones(&grid)?
+				.into_iter()
+				.map(|(x, y, w, h)| [percent(x), percent(y), percent(w), percent(h)])
+				.collect()
+		}
+		kind => {
+			return Err(format!(
+				"FancyZones layout '{}' is of unknown type '{}'",
+				custom.name, kind
+			))
+		}
+	};
+	let mut layout = Layout {
+		version: Some(LAYOUT_VERSION),
+		..Layout::new()
+	};
+	for (i, [x, y, w, h]) in zones.into_iter().enumerate() {
+		let rule = Window {
+			name: Some(format!("zone-{}", i + 1)),
+			x: Some(x.to_string()),
+			y: Some(y.to_string()),
+			w: Some(w.to_string()),
+			h: Some(h.to_string()),
+			..Window::new()
+		};
+		layout.add_rule(None, rule);
+	}
+	Ok(layout)
+}
+
+/// A container of the i3 tree, as dumped by `i3-msg -t get_tree`.
+#[derive(Debug, Deserialize)]
+struct I3Node {
+	name: Option<String>,
+	#[serde(rename = "type")]
+	kind: String,
+	rect: I3Rect,
+	/// The number of a workspace, or -1 if its name does not start with one.
+	num: Option<i32>,
+	/// The X11 window id of a window.
+	window: Option<u64>,
+	window_properties: Option<I3WindowProperties>,
+	#[serde(default)]
+	sticky: bool,
+	#[serde(default)]
+	nodes: Vec<I3Node>,
+	#[serde(default)]
+	floating_nodes: Vec<I3Node>,
+}
+
+#[derive(Debug, Deserialize)]
+struct I3Rect {
+	x: i32,
+	y: i32,
+	width: i32,
+	height: i32,
+}
+
+impl From<&I3Rect> for Rect {
+	fn from(rect: &I3Rect) -> Self {
+		Rect::new(rect.x, rect.y, rect.width, rect.height)
+	}
+}
+
+#[derive(Debug, Deserialize)]
+struct I3WindowProperties {
+	class: Option<String>,
+}
+
+/// Collects the windows in `node`, found on `desktop`.
+fn i3_windows(node: &I3Node, desktop: &Option<Desktop>, windows: &mut Vec<Window>) {
+	let desktop = match (node.kind.as_str(), node.num) {
+		("workspace", Some(num)) if num > 0 => Some(Desktop::Index(num as u32 - 1)),
+		("workspace", _) => node.name.clone().map(Desktop::Name),
+		_ => desktop.clone(),
+	};
+	if node.window.is_some() {
+		let rect = Rect::from(&node.rect);
+		windows.push(Window {
+			title: node.name.clone(),
+			process: node
+				.window_properties
+				.as_ref()
+				.and_then(|p| p.class.as_deref())
+				.map(str::to_lowercase),
+			x: Some(rect.x.to_string()),
+			y: Some(rect.y.to_string()),
+			w: Some(rect.w.to_string()),
+			h: Some(rect.h.to_string()),
+			desktop: desktop.clone().filter(|_| !node.sticky),
+			sticky: Some(node.sticky).filter(|sticky| *sticky),
+			..Window::new()
+		});
+	}
+	for child in node.nodes.iter().chain(&node.floating_nodes) {
+		i3_windows(child, &desktop, windows);
+	}
+}
+
+/// Builds a layout from the i3 tree, as dumped by `i3-msg -t get_tree`, like `wlm snapshot`
+/// does from the windows on screen: a screen for each output, with a rule moving each window
+/// back to where it is and to its workspace. Processes are matched by the window class, in
+/// lower case.
+///
+/// # Errors
+///
+/// Returns an error if `content` is not an i3 tree.
+pub fn i3_tree(content: &str) -> Result<Layout, String> {
+	let root: I3Node =
+		serde_json::from_str(content).map_err(|e| format!("Failed to read the i3 tree: {}", e))?;
+	let screens = root
+		.nodes
+		.iter()
+		// The __i3 output holds the scratchpad.
+		.filter(|output| output.kind == "output" && output.name.as_deref() != Some("__i3"))
+		.enumerate()
+		.map(|(id, output)| {
+			let mut windows = Vec::new();
+			i3_windows(output, &None, &mut windows);
+			Screen {
+				id: Some(id as u8),
+				name: output.name.clone(),
+				bounds: Some(Rect::from(&output.rect)),
+				windows,
+				..Screen::new()
+			}
+		})
+		.collect::<Vec<_>>();
+	Ok(snapshot(&screens, false))
+}
+
+/// Builds a layout from the output of `wmctrl -lGpx`, like `wlm snapshot` does from the windows
+/// on screen: a rule moving each window back to where it is and to its desktop. Processes are
+/// matched by the window class, in lower case.
+///
+/// # Errors
+///
+/// Returns an error if a line is not as written by `wmctrl -lGpx`.
+///
+/// # Examples
+///
+/// ```
+/// # use wlm::import::wmctrl;
+/// let content = "0x03a00003  1 4242   0    0    960  1080 Mail.thunderbird  host Inbox - Thunderbird\n";
+/// let rule = &wmctrl(content).unwrap().screens[0].windows[0];
+/// assert_eq!(Some(r"^Inbox \- Thunderbird$".to_string()), rule.title);
+/// assert_eq!(Some("thunderbird".to_string()), rule.process);
+/// assert_eq!(Some("960".to_string()), rule.w);
+/// ```
+pub fn wmctrl(content: &str) -> Result<Layout, String> {
+	let mut windows = Vec::new();
+	for (number, line) in content.lines().enumerate() {
+		if line.trim().is_empty() {
+			continue;
+		}
+		let invalid = || {
+			format!(
+				"Line {} is not as written by wmctrl -lGpx: {}",
+				number + 1,
+				line
+			)
+		};
+		// The id, desktop, pid, x, y, width, height, class and machine, then the title.
+		let mut fields = Vec::new();
+		let mut rest = line.trim();
+		for _ in 0..9 {
+			let (field, tail) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+			if field.is_empty() {
+				return Err(invalid());
+			}
+			fields.push(field);
+			rest = tail.trim_start();
+		}
+		let desktop = fields[1].parse::<i64>().map_err(|_| invalid())?;
+		let mut geometry = Vec::new();
+		for field in &fields[3..7] {
+			geometry.push(field.parse::<i32>().map_err(|_| invalid())?);
+		}
+		// The class is written as instance.Class.
+		let class = fields[7].rsplit('.').next().unwrap_or(fields[7]);
+		windows.push(Window {
+			title: Some(rest.to_string()),
+			process: Some(class.to_lowercase()),
+			x: Some(geometry[0].to_string()),
+			y: Some(geometry[1].to_string()),
+			w: Some(geometry[2].to_string()),
+			h: Some(geometry[3].to_string()),
+			desktop: u32::try_from(desktop).ok().map(Desktop::Index),
+			// wmctrl lists the desktop of sticky windows as -1.
+			sticky: Some(true).filter(|_| desktop < 0),
+			..Window::new()
+		});
+	}
+	let screen = Screen {
+		windows,
+		..Screen::new()
+	};
+	Ok(snapshot(&[screen], false))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn fancyzones_grid() {
+		let content = r#"{"custom-layouts": [
+			{"name": "Focus", "type": "canvas", "info": {"ref-width": 1920, "ref-height": 1080, "zones": []}},
+			{"name": "Coding", "type": "grid", "info": {
+				"rows": 2, "columns": 3,
+				"rows-percentage": [5000, 5000],
+				"columns-percentage": [2500, 5000, 2500],
+				"cell-child-map": [[0, 1, 2], [0, 1, 3]],
+				"show-spacing": true, "spacing": 16, "sensitivity-radius": 20
+			}}
+		]}"#;
+		let layout = fancyzones(content, Some("Coding")).unwrap();
+		let zones = &layout.screens[0].windows;
+		assert_eq!(4, zones.len());
+		let geometry = |zone: &Window| {
+			[&zone.x, &zone.y, &zone.w, &zone.h]
+				.map(|length| length.clone().unwrap())
+				.join(" ")
+		};
+		assert_eq!("0% 0% 25% 100%", geometry(&zones[0]));
+		assert_eq!("25% 0% 50% 100%", geometry(&zones[1]));
+		assert_eq!("75% 50% 25% 50%", geometry(&zones[3]));
+		assert_eq!(Some("zone-4".to_string()), zones[3].name);
+		assert!(fancyzones(content, None).unwrap().screens.is_empty());
+		assert!(fancyzones(content, Some("Gaming"))
+			.unwrap_err()
+			.contains(r#"["Focus", "Coding"]"#));
+	}
+
+	#[test]
+	fn i3_tree_outputs_and_workspaces() {
+		let content = r#"{"name": "root", "type": "root", "rect": {"x": 0, "y": 0, "width": 3840, "height": 1080},
+			"nodes": [
+				{"name": "__i3", "type": "output", "rect": {"x": 0, "y": 0, "width": 3840, "height": 1080},
+					"nodes": [{"name": "__i3_scratch", "type": "workspace", "num": -1,
+						"rect": {"x": 0, "y": 0, "width": 0, "height": 0},
+						"floating_nodes": [{"name": "Scratch", "type": "floating_con", "window": 3,
+							"rect": {"x": 0, "y": 0, "width": 640, "height": 480}}]}]},
+				{"name": "DP-1", "type": "output", "rect": {"x": 1920, "y": 0, "width": 1920, "height": 1080},
+					"nodes": [{"name": "content", "type": "con", "rect": {"x": 1920, "y": 0, "width": 1920, "height": 1080},
+						"nodes": [
+							{"name": "2: web", "type": "workspace", "num": 2, "rect": {"x": 1920, "y": 0, "width": 1920, "height": 1080},
+								"nodes": [{"name": "Inbox - Thunderbird", "type": "con", "window": 1,
+									"window_properties": {"class": "thunderbird", "instance": "Mail"},
+									"rect": {"x": 1920, "y": 0, "width": 960, "height": 1080}}]},
+							{"name": "chat", "type": "workspace", "num": -1, "rect": {"x": 1920, "y": 0, "width": 1920, "height": 1080},
+								"floating_nodes": [{"name": "Slack", "type": "floating_con", "window": 2, "sticky": true,
+									"window_properties": {"class": "Slack"},
+									"rect": {"x": 2000, "y": 100, "width": 800, "height": 600}}]}
+						]}]}
+			]}"#;
+		let layout = i3_tree(content).unwrap();
+		assert_eq!(1, layout.screens.len());
+		let screen = &layout.screens[0];
+		assert_eq!(Some("DP-1".to_string()), screen.name);
+		assert_eq!(Some(Rect::new(1920, 0, 1920, 1080)), screen.bounds);
+		assert_eq!(2, screen.windows.len());
+		let inbox = &screen.windows[0];
+		assert_eq!(Some(r"^Inbox \- Thunderbird$".to_string()), inbox.title);
+		assert_eq!(Some("thunderbird".to_string()), inbox.process);
+		assert_eq!(Some(Desktop::Index(1)), inbox.desktop);
+		assert_eq!(Some("1920".to_string()), inbox.x);
+		let slack = &screen.windows[1];
+		assert_eq!(Some("slack".to_string()), slack.process);
+		assert_eq!(Some(true), slack.sticky);
+		assert_eq!(None, slack.desktop);
+		assert_eq!(1, layout.match_displays.len());
+	}
+
+	#[test]
+	fn wmctrl_lines() {
+		let content = "\
+0x01e00003 -1 1234   0    0    1920 1080 desktop_window.Nautilus  host Desktop
+0x03a00003  0 4242   0    24   1280 1056 Navigator.firefox  host Mozilla  Firefox
+0x04200007  2 4343   1280 24   640  1056 slack.Slack  host Slack | general
+0x04600003 -1 4444   100  100  400  300  keepassxc.KeePassXC  host Passwords
+";
+		let rules = wmctrl(content).unwrap().screens.remove(0).windows;
+		assert_eq!(3, rules.len());
+		assert_eq!(Some("^Mozilla  Firefox$".to_string()), rules[0].title);
+		assert_eq!(Some("firefox".to_string()), rules[0].process);
+		assert_eq!(Some(Desktop::Index(0)), rules[0].desktop);
+		assert_eq!(Some("24".to_string()), rules[0].y);
+		assert_eq!(Some(r"^Slack \| general$".to_string()), rules[1].title);
+		assert_eq!(Some(Desktop::Index(2)), rules[1].desktop);
+		assert_eq!(Some(true), rules[2].sticky);
+		assert_eq!(None, rules[2].desktop);
+		assert!(wmctrl("0x03a00003 0 4242 0 24 1280\n").is_err());
+		assert!(wmctrl("0x03a00003 zero 4242 0 24 1280 1056 a.b host Title\n").is_err());
+	}
+}
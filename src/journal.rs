@@ -0,0 +1,286 @@
+use crate::layout::{Layout, Screen, Window};
+use crate::snapshot::rule_for;
+
+use directories::ProjectDirs;
+use std::path::{Path, PathBuf};
+
+/// The number of applied layouts that can be undone.
+pub const HISTORY_LIMIT: usize = 20;
+
+/// A layout that was applied, along with a layout moving the windows it affected back to where
+/// they were.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+	pub applied: Layout,
+	pub previous: Layout,
+	/// The ids of the windows the rules of `previous` were recorded from, in order, to move the
+	/// same windows back even if their title changed meanwhile.
+	#[serde(default)]
+	pub ids: Vec<Option<u64>>,
+}
+
+/// The history of applied layouts, to undo and redo them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Journal {
+	/// The applied layouts that can be undone, the latest last.
+	#[serde(default)]
+	undo: Vec<Entry>,
+	/// The undone layouts that can be applied again, the latest undone last.
+	#[serde(default)]
+	redo: Vec<Entry>,
+}
+
+impl Journal {
+	/// Returns the file the journal is kept in, in the platform-specific data location, e.g.
+	/// `~/.local/share/wlm/journal.json`.
+	pub fn default_file() -> Option<PathBuf> {
+		ProjectDirs::from("com", "wlm", "wlm").map(|dirs| dirs.data_dir().join("journal.json"))
+	}
+
+	/// Loads the journal kept in `journal_file`, which is empty if the file does not exist.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the file cannot be read or is not a journal.
+	pub fn load(journal_file: &Path) -> Result<Journal, String> {
+		if !journal_file.exists() {
+			return Ok(Journal::default());
+		}
+		let content = std::fs::read_to_string(journal_file)
+			.map_err(|e| format!("Failed to read {}: {}", journal_file.display(), e))?;
+		serde_json::from_str(&content)
+			.map_err(|e| format!("Failed to load {}: {}", journal_file.display(), e))
+	}
+
+	/// Keeps the journal in `journal_file`, creating its directory if need be.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the file cannot be written.
+	pub fn save(&self, journal_file: &Path) -> Result<(), String> {
+		if let Some(dir) = journal_file.parent() {
+			std::fs::create_dir_all(dir)
+				.map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+		}
+		std::fs::write(journal_file, serde_json::to_string(self).unwrap())
+			.map_err(|e| format!("Failed to write {}: {}", journal_file.display(), e))
+	}
+
+	/// Records that `applied` was applied to the windows on `screens`, as listed by
+	/// `WindowProvider::screens` right before, forgetting what was undone and the oldest entry
+	/// beyond `HISTORY_LIMIT`.
+	pub fn record(&mut self, applied: Layout, screens: &[Screen]) {
+		let mut previous = previous_layout(&applied, screens);
+		// Layouts do not keep window ids, so they are kept alongside.
+		let ids = rules(&mut previous).map(|rule| rule.id.take()).collect();
+		self.undo.push(Entry {
+			applied,
+			previous,
+			ids,
+		});
+		if self.undo.len() > HISTORY_LIMIT {
+			self.undo.remove(0);
+		}
+		self.redo.clear();
+	}
+
+	/// Returns the layout undoing the latest applied layout, which can then be redone.
+	///
+	/// Its rules move back the windows they were recorded from, if still on `screens`, as listed
+	/// by `WindowProvider::screens`, and otherwise the first window they match by title and
+	/// process.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use wlm::journal::Journal;
+	/// # use wlm::layout::Layout;
+	/// let mut journal = Journal::default();
+	/// journal.record(Layout::new(), &[]);
+	/// assert!(journal.undo(&[]).is_some());
+	/// assert!(journal.undo(&[]).is_none());
+	/// assert!(journal.redo().is_some());
+	/// assert!(journal.redo().is_none());
+	/// ```
+	pub fn undo(&mut self, screens: &[Screen]) -> Option<Layout> {
+		let entry = self.undo.pop()?;
+		let mut previous = entry.previous.clone();
+		let live = screens
+			.iter()
+			.flat_map(|screen| &screen.windows)
+			.filter_map(|window| window.id)
+			.collect::<Vec<u64>>();
+		for (rule, id) in rules(&mut previous).zip(&entry.ids) {
+			rule.id = id.filter(|id| live.contains(id));
+		}
+		self.redo.push(entry);
+		Some(previous)
+	}
+
+	/// Returns the latest undone layout to apply again, which can then be undone again.
+	pub fn redo(&mut self) -> Option<Layout> {
+		let entry = self.redo.pop()?;
+		let applied = entry.applied.clone();
+		self.undo.push(entry);
+		Some(applied)
+	}
+}
+
+/// Returns the rules of `layout`, screen by screen.
+fn rules(layout: &mut Layout) -> impl Iterator<Item = &mut Window> {
+	layout
+		.screens
+		.iter_mut()
+		.flat_map(|screen| &mut screen.windows)
+}
+
+/// Returns a layout moving the windows on `screens` that applying `layout` affects back to where
+/// they are, each rule applying to the first window it matches and carrying the `id` of the
+/// window.
+pub fn previous_layout(layout: &Layout, screens: &[Screen]) -> Layout {
+	let mut previous = Layout::new();
+	let mut affected = Vec::new();
+	for rule in layout.screens.iter().flat_map(|s| &s.windows) {
+		let matched = screens
+			.iter()
+			.flat_map(|screen| screen.windows.iter().map(move |w| (screen, w)))
+			.find(|(_, window)| rule.matches(window));
+		if let Some((screen, window)) = matched {
+			if affected.contains(&window) {
+				continue;
+			}
+			affected.push(window);
+			// The state is recorded even where it is unset, so undo also restores a window the
+			// layout maximized or minimized.
+			let mut rule = rule_for(window);
+			rule.id = window.id;
+			rule.maximized = Some(window.maximized == Some(true));
+			rule.maximized_vertical = Some(window.maximized_vertical == Some(true));
+			rule.maximized_horizontal = Some(window.maximized_horizontal == Some(true));
+			rule.minimized = Some(window.minimized == Some(true));
+			previous.add_rule(screen.id, rule);
+		}
+	}
+	previous
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::layout::{Format, ScreenBuilder, WindowBuilder};
+	use assert_fs::TempDir;
+
+	fn screens() -> Vec<Screen> {
+		let live = |id: u64, title: &str, process: &str, x: &str| {
+			WindowBuilder::default()
+				.id(Some(id))
+				.title(Some(title.to_string()))
+				.process(Some(process.to_string()))
+				.x(Some(x.to_string()))
+				.y(Some("0".to_string()))
+				.w(Some("800".to_string()))
+				.h(Some("600".to_string()))
+				.build()
+				.unwrap()
+		};
+		vec![ScreenBuilder::default()
+			.id(1)
+			.windows(vec![
+				live(0x3a00007, "main.rs - Code", "/usr/share/code/code", "100"),
+				live(0x4200003, "Slack", "/usr/lib/slack/slack", "200"),
+			])
+			.build()
+			.unwrap()]
+	}
+
+	fn layout() -> Layout {
+		Layout::parse(
+			"screens:\n- windows:\n  - process: code\n    x: 0\n  - title: Code\n    w: 50%\n  - process: alacritty\n",
+			Format::Yaml,
+		)
+		.unwrap()
+	}
+
+	#[test]
+	fn previous_layout_of_affected_windows() {
+		let previous = previous_layout(&layout(), &screens());
+		assert_eq!(1, previous.screens.len());
+		assert_eq!(Some(1), previous.screens[0].id);
+		let rules = &previous.screens[0].windows;
+		assert_eq!(1, rules.len());
+		assert_eq!(Some(r"^main\.rs \- Code$".to_string()), rules[0].title);
+		assert_eq!(Some("100".to_string()), rules[0].x);
+		assert_eq!(Some("800".to_string()), rules[0].w);
+		assert_eq!(Some(false), rules[0].maximized);
+		assert_eq!(Some(false), rules[0].minimized);
+	}
+
+	#[test]
+	fn previous_layout_keeps_state() {
+		let mut screens = screens();
+		screens[0].windows[0].maximized_vertical = Some(true);
+		let previous = previous_layout(&layout(), &screens);
+		let rule = &previous.screens[0].windows[0];
+		assert_eq!(Some(false), rule.maximized);
+		assert_eq!(Some(true), rule.maximized_vertical);
+		assert_eq!(Some(false), rule.maximized_horizontal);
+	}
+
+	#[test]
+	fn history_is_bounded() {
+		let mut journal = Journal::default();
+		for _ in 0..HISTORY_LIMIT + 5 {
+			journal.record(layout(), &screens());
+		}
+		let mut undone = 0;
+		while journal.undo(&screens()).is_some() {
+			undone += 1;
+		}
+		assert_eq!(HISTORY_LIMIT, undone);
+	}
+
+	#[test]
+	fn record_forgets_redo() {
+		let mut journal = Journal::default();
+		journal.record(layout(), &screens());
+		journal.undo(&screens());
+		journal.record(Layout::new(), &screens());
+		assert!(journal.redo().is_none());
+	}
+
+	#[test]
+	fn undo_moves_back_the_same_window() {
+		let mut journal = Journal::default();
+		journal.record(layout(), &screens());
+		let mut live = screens();
+		live[0].windows[0].title = Some("lib.rs - Code".to_string());
+		let previous = journal.undo(&live).unwrap();
+		let rule = &previous.screens[0].windows[0];
+		assert_eq!(Some(0x3a00007), rule.id);
+		assert!(rule.matches(&live[0].windows[0]));
+		assert!(!rule.matches(&screens()[0].windows[1]));
+	}
+
+	#[test]
+	fn undo_matches_by_title_once_the_window_is_gone() {
+		let mut journal = Journal::default();
+		journal.record(layout(), &screens());
+		let mut live = screens();
+		live[0].windows[0].id = Some(0x5c00001);
+		let previous = journal.undo(&live).unwrap();
+		let rule = &previous.screens[0].windows[0];
+		assert_eq!(None, rule.id);
+		assert!(rule.matches(&live[0].windows[0]));
+	}
+
+	#[test]
+	fn save_and_load() {
+		let temp = TempDir::new().unwrap();
+		let journal_file = temp.path().join("data").join("journal.json");
+		assert_eq!(Journal::default(), Journal::load(&journal_file).unwrap());
+		let mut journal = Journal::default();
+		journal.record(layout(), &screens());
+		journal.save(&journal_file).unwrap();
+		assert_eq!(journal, Journal::load(&journal_file).unwrap());
+	}
+}
@@ -1,10 +1,31 @@
-pub static FORMAT_NAMES: [&str; 2] = ["table", "yaml"];
+use crate::condition::{Context, When};
+use crate::fingerprint::DisplayMatcher;
+
+use regex::Regex;
+use std::collections::BTreeMap;
+
+pub static FORMAT_NAMES: [&str; 8] = [
+	"table", "yaml", "json", "toml", "ron", "ndjson", "csv", "tsv",
+];
+/// The file extensions layouts are searched for, in order of precedence.
+pub static LAYOUT_EXTENSIONS: [&str; 5] = ["yml", "yaml", "json", "toml", "ron"];
+/// The version of the layout format, written as the `version` of a layout.
+pub const LAYOUT_VERSION: u32 = 1;
 pub const MAX_WINDOW_TITLE_LENGTH: usize = 128;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Format {
 	Table,
 	Yaml,
+	Json,
+	Toml,
+	Ron,
+	/// One JSON object per line, only for listing windows.
+	Ndjson,
+	/// Comma-separated values, only for listing windows.
+	Csv,
+	/// Tab-separated values, only for listing windows.
+	Tsv,
 }
 
 impl Format {
@@ -14,6 +35,59 @@ impl Format {
 	pub fn as_str(&self) -> &'static str {
 		FORMAT_NAMES[*self as usize]
 	}
+
+	/// Returns the file extension of layouts written in this `Format`, if it is a layout format.
+	pub fn extension(&self) -> Option<&'static str> {
+		match self {
+			Format::Table | Format::Ndjson | Format::Csv | Format::Tsv => None,
+			Format::Yaml => Some("yml"),
+			Format::Json => Some("json"),
+			Format::Toml => Some("toml"),
+			Format::Ron => Some("ron"),
+		}
+	}
+
+	/// Returns the layout `Format` of a file with the given extension.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use wlm::layout::Format;
+	/// assert_eq!(Some(Format::Yaml), Format::from_extension("yaml"));
+	/// assert_eq!(Some(Format::Toml), Format::from_extension("toml"));
+	/// assert_eq!(None, Format::from_extension("txt"));
+	/// ```
+	pub fn from_extension<S: AsRef<str>>(extension: S) -> Option<Format> {
+		match extension.as_ref() {
+			"yml" | "yaml" => Some(Format::Yaml),
+			"json" => Some(Format::Json),
+			"toml" => Some(Format::Toml),
+			"ron" => Some(Format::Ron),
+			_ => None,
+		}
+	}
+
+	/// Serializes `value` as this format, e.g. a layout or the output of a command.
+	///
+	/// # Errors
+	///
+	/// Returns an error if this format only lists windows, e.g. `table` or `csv`, or the
+	/// serialization fails.
+	pub fn serialize<T: serde::Serialize>(&self, value: &T) -> Result<String, String> {
+		match self {
+			Format::Yaml => serde_yaml::to_string(value).map_err(|e| e.to_string()),
+			Format::Json => serde_json::to_string_pretty(value)
+				.map(|json| json + "\n")
+				.map_err(|e| e.to_string()),
+			Format::Toml => toml::to_string(value).map_err(|e| e.to_string()),
+			Format::Ron => ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+				.map(|ron| ron + "\n")
+				.map_err(|e| e.to_string()),
+			Format::Table | Format::Ndjson | Format::Csv | Format::Tsv => {
+				Err(format!("Cannot serialize as {}", self))
+			}
+		}
+	}
 }
 
 impl std::str::FromStr for Format {
@@ -23,6 +97,12 @@ impl std::str::FromStr for Format {
 		match format {
 			"table" => Ok(Format::Table),
 			"yaml" => Ok(Format::Yaml),
+			"json" => Ok(Format::Json),
+			"toml" => Ok(Format::Toml),
+			"ron" => Ok(Format::Ron),
+			"ndjson" => Ok(Format::Ndjson),
+			"csv" => Ok(Format::Csv),
+			"tsv" => Ok(Format::Tsv),
 			_ => Err(format!("Failed to parse into string '{format}'")),
 		}
 	}
@@ -34,19 +114,845 @@ impl std::fmt::Display for Format {
 	}
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Builder)]
+/// A length along one axis of a monitor, as written in a layout (e.g. `"940"`, `"940px"` or `"40%"`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Length {
+	Pixels(i32),
+	Percent(f64),
+}
+
+impl Length {
+	/// Converts the length into pixels, resolving percentages against `extent`.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use wlm::layout::Length;
+	/// assert_eq!(Length::Percent(50.0).to_pixels(1920), 960);
+	/// assert_eq!(Length::Pixels(200).to_pixels(1920), 200);
+	/// ```
+	pub fn to_pixels(&self, extent: i32) -> i32 {
+		match self {
+			Length::Pixels(pixels) => *pixels,
+			Length::Percent(percent) => (f64::from(extent) * percent / 100.0).round() as i32,
+		}
+	}
+
+	/// Converts the length into a position along an axis, in pixels of the virtual screen
+	/// spanning every monitor. Percentages are of the monitor spanning `extent` from `origin`,
+	/// while pixels are already positions on the virtual screen.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use wlm::layout::Length;
+	/// assert_eq!(Length::Percent(50.0).to_position(1920, 2560), 3200);
+	/// assert_eq!(Length::Pixels(200).to_position(1920, 2560), 200);
+	/// ```
+	pub fn to_position(&self, origin: i32, extent: i32) -> i32 {
+		match self {
+			Length::Pixels(pixels) => *pixels,
+			Length::Percent(_) => origin + self.to_pixels(extent),
+		}
+	}
+
+	/// Returns `pixels` as a percentage of `extent`, rounded to two decimals.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use wlm::layout::Length;
+	/// assert_eq!(Length::percent_of(960, 1920), Length::Percent(50.0));
+	/// assert_eq!(Length::percent_of(640, 1920), Length::Percent(33.33));
+	/// ```
+	pub fn percent_of(pixels: i32, extent: i32) -> Length {
+		let percent = f64::from(pixels) * 100.0 / f64::from(extent.max(1));
+		Length::Percent((percent * 100.0).round() / 100.0)
+	}
+}
+
+impl std::str::FromStr for Length {
+	type Err = String;
+
+	fn from_str(value: &str) -> Result<Length, Self::Err> {
+		let value = value.trim();
+		if let Some(percent) = value.strip_suffix('%') {
+			percent
+				.trim()
+				.parse::<f64>()
+				.map(Length::Percent)
+				.map_err(|_| format!("Invalid percentage '{value}'"))
+		} else {
+			value
+				.strip_suffix("px")
+				.unwrap_or(value)
+				.trim()
+				.parse::<i32>()
+				.map(Length::Pixels)
+				.map_err(|_| format!("Invalid length '{value}'"))
+		}
+	}
+}
+
+impl std::fmt::Display for Length {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Length::Pixels(pixels) => write!(f, "{}", pixels),
+			Length::Percent(percent) => write!(f, "{}%", percent),
+		}
+	}
+}
+
+#[cfg(test)]
+mod test_length {
+	use super::*;
+
+	#[test]
+	fn parse_pixels() {
+		assert_eq!(Ok(Length::Pixels(940)), "940".parse());
+	}
+
+	#[test]
+	fn parse_pixels_with_suffix() {
+		assert_eq!(Ok(Length::Pixels(940)), "940px".parse());
+	}
+
+	#[test]
+	fn parse_negative_pixels() {
+		assert_eq!(Ok(Length::Pixels(-8)), "-8".parse());
+	}
+
+	#[test]
+	fn parse_percent() {
+		assert_eq!(Ok(Length::Percent(40.0)), "40%".parse());
+	}
+
+	#[test]
+	fn parse_invalid() {
+		assert!("forty".parse::<Length>().is_err());
+		assert!("%".parse::<Length>().is_err());
+	}
+
+	#[test]
+	fn percent_to_pixels() {
+		assert_eq!(768, Length::Percent(40.0).to_pixels(1920));
+	}
+
+	#[test]
+	fn percent_of_round_trips() {
+		let percent = Length::percent_of(1067, 2560);
+		assert_eq!(Length::Percent(41.68), percent);
+		assert_eq!(1067, percent.to_pixels(2560));
+	}
+}
+
+/// A virtual desktop (workspace), addressed either by its zero-based index or by its name.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(untagged)]
+pub enum Desktop {
+	Index(u32),
+	Name(String),
+}
+
+impl Desktop {
+	/// Resolves the desktop into an index given the names of the available desktops.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use wlm::layout::Desktop;
+	/// let names = vec!["main".to_string(), "comms".to_string()];
+	/// assert_eq!(Desktop::Name("comms".into()).resolve(&names), Some(1));
+	/// assert_eq!(Desktop::Index(1).resolve(&names), Some(1));
+	/// assert_eq!(Desktop::Index(2).resolve(&names), None);
+	/// ```
+	pub fn resolve(&self, names: &[String]) -> Option<u32> {
+		match self {
+			Desktop::Index(index) if (*index as usize) < names.len() => Some(*index),
+			Desktop::Index(_) => None,
+			Desktop::Name(name) => names.iter().position(|n| n == name).map(|i| i as u32),
+		}
+	}
+}
+
+impl std::fmt::Display for Desktop {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Desktop::Index(index) => write!(f, "{}", index),
+			Desktop::Name(name) => f.pad(name),
+		}
+	}
+}
+
+impl From<u32> for Desktop {
+	fn from(index: u32) -> Self {
+		Desktop::Index(index)
+	}
+}
+
+impl From<&str> for Desktop {
+	fn from(name: &str) -> Self {
+		Desktop::Name(name.to_string())
+	}
+}
+
+/// A rectangle in screen coordinates.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Rect {
+	pub x: i32,
+	pub y: i32,
+	pub w: i32,
+	pub h: i32,
+}
+
+impl Rect {
+	pub fn new(x: i32, y: i32, w: i32, h: i32) -> Self {
+		Rect { x, y, w, h }
+	}
+
+	/// Returns whether this rectangle and `other` share any area.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use wlm::layout::Rect;
+	/// let monitor = Rect::new(0, 0, 1920, 1080);
+	/// assert!(monitor.intersects(&Rect::new(1900, 1000, 100, 100)));
+	/// assert!(!monitor.intersects(&Rect::new(1920, 0, 100, 100)));
+	/// ```
+	pub fn intersects(&self, other: &Rect) -> bool {
+		self.x < other.x + other.w
+			&& other.x < self.x + self.w
+			&& self.y < other.y + other.h
+			&& other.y < self.y + self.h
+	}
+
+	/// Returns the area this rectangle and `other` share, if any.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use wlm::layout::Rect;
+	/// let monitor = Rect::new(1920, 0, 2560, 1440);
+	/// let work_area = Rect::new(0, 32, 4480, 1408);
+	/// assert_eq!(Some(Rect::new(1920, 32, 2560, 1408)), monitor.intersection(&work_area));
+	/// assert_eq!(None, monitor.intersection(&Rect::new(0, 0, 1920, 1080)));
+	/// ```
+	pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+		if !self.intersects(other) {
+			return None;
+		}
+		let x = self.x.max(other.x);
+		let y = self.y.max(other.y);
+		Some(Rect::new(
+			x,
+			y,
+			(self.x + self.w).min(other.x + other.w) - x,
+			(self.y + self.h).min(other.y + other.h) - y,
+		))
+	}
+}
+
+impl std::fmt::Display for Rect {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}x{}+{}+{}", self.w, self.h, self.x, self.y)
+	}
+}
+
+/// An error parsing a layout, located in the layout when the parser reports where it failed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+	pub message: String,
+	/// The 1-based line and column the error occurred at.
+	pub location: Option<(usize, usize)>,
+}
+
+impl ParseError {
+	fn new<S: Into<String>>(message: S, location: Option<(usize, usize)>) -> Self {
+		let message = message.into();
+		// Most parsers append the location to the message, which is reported separately.
+		let message = Regex::new(r" at line \d+ column \d+$")
+			.unwrap()
+			.replace(&message, "")
+			.into_owned();
+		ParseError { message, location }
+	}
+}
+
+impl std::fmt::Display for ParseError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.location {
+			Some((line, column)) => write!(f, "{}:{}: {}", line, column, self.message),
+			None => write!(f, "{}", self.message),
+		}
+	}
+}
+
+/// Deserializes an optional string that may also be written as a number, e.g. `w: 940`.
+pub(crate) fn string_or_number<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+	D: serde::Deserializer<'de>,
+{
+	struct Visitor;
+
+	impl<'de> serde::de::Visitor<'de> for Visitor {
+		type Value = Option<String>;
+
+		fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			f.write_str("a length such as 940, '940px' or '40%'")
+		}
+
+		fn visit_none<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+			Ok(None)
+		}
+
+		fn visit_unit<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+			Ok(None)
+		}
+
+		fn visit_some<D: serde::Deserializer<'de>>(self, d: D) -> Result<Self::Value, D::Error> {
+			d.deserialize_any(self)
+		}
+
+		fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Self::Value, E> {
+			Ok(Some(value.to_string()))
+		}
+
+		fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<Self::Value, E> {
+			Ok(Some(value.to_string()))
+		}
+
+		fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<Self::Value, E> {
+			Ok(Some(value.to_string()))
+		}
+
+		fn visit_f64<E: serde::de::Error>(self, value: f64) -> Result<Self::Value, E> {
+			Ok(Some(value.to_string()))
+		}
+	}
+
+	deserializer.deserialize_option(Visitor)
+}
+
+/// Deserializes a value reported when listing windows that is not part of a layout, e.g. the id
+/// of a window, as nothing, so the output of `wlm ls` can be applied as a layout.
+fn ignored<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+	D: serde::Deserializer<'de>,
+{
+	<serde::de::IgnoredAny as serde::Deserialize>::deserialize(deserializer).map(|_| None)
+}
+
+/// Describes a length written as a number or as a string such as `"940px"` or `"40%"`.
+fn length_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+	serde_json::from_value(serde_json::json!({
+		"type": ["string", "integer", "null"],
+		"pattern": r"^\s*-?(\d+\s*(px)?|\d+(\.\d+)?\s*%)\s*$",
+	}))
+	.unwrap()
+}
+
+/// Describes the variables of a layout, whose values may be written as strings or numbers.
+fn vars_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+	serde_json::from_value(serde_json::json!({
+		"type": "object",
+		"additionalProperties": { "type": ["string", "number", "boolean"] },
+	}))
+	.unwrap()
+}
+
+/// Deserializes the variables of a layout, accepting numbers and booleans as values, e.g.
+/// `sidebar: 400`.
+fn vars<'de, D>(deserializer: D) -> Result<BTreeMap<String, String>, D::Error>
+where
+	D: serde::Deserializer<'de>,
+{
+	#[derive(Deserialize)]
+	#[serde(untagged)]
+	enum Value {
+		String(String),
+		Integer(i64),
+		Float(f64),
+		Bool(bool),
+	}
+
+	Ok(
+		<BTreeMap<String, Value> as serde::Deserialize>::deserialize(deserializer)?
+			.into_iter()
+			.map(|(name, value)| {
+				let value = match value {
+					Value::String(value) => value,
+					Value::Integer(value) => value.to_string(),
+					Value::Float(value) => value.to_string(),
+					Value::Bool(value) => value.to_string(),
+				};
+				(name, value)
+			})
+			.collect(),
+	)
+}
+
+/// Substitutes `${name}` with the variable `name` of `vars` and `${env:NAME}` with the
+/// environment variable `NAME`, as looked up by `env`, in `value`. `$$` is a literal `$`, and a
+/// `$` followed by anything else is left as is, e.g. to anchor regular expressions.
+///
+/// # Errors
+///
+/// Returns an error if a variable is undefined or not terminated.
+///
+/// # Examples
+///
+/// ```
+/// # use std::collections::BTreeMap;
+/// # use wlm::layout::interpolate;
+/// let vars = BTreeMap::from([("sidebar".to_string(), "30%".to_string())]);
+/// let env = |name: &str| (name == "HOME").then(|| "/home/me".to_string());
+/// assert_eq!(Ok("30%".to_string()), interpolate("${sidebar}", &vars, env));
+/// assert_eq!(Ok("/home/me/src$".to_string()), interpolate("${env:HOME}/src$", &vars, env));
+/// assert_eq!(Ok("${sidebar}".to_string()), interpolate("$${sidebar}", &vars, env));
+/// assert!(interpolate("${width}", &vars, env).is_err());
+/// ```
+pub fn interpolate<F>(
+	value: &str,
+	vars: &BTreeMap<String, String>,
+	env: F,
+) -> Result<String, String>
+where
+	F: Fn(&str) -> Option<String>,
+{
+	let mut interpolated = String::with_capacity(value.len());
+	let mut rest = value;
+	while let Some(start) = rest.find('$') {
+		interpolated.push_str(&rest[..start]);
+		rest = &rest[start..];
+		if let Some(after) = rest.strip_prefix("$$") {
+			interpolated.push('$');
+			rest = after;
+		} else if let Some(after) = rest.strip_prefix("${") {
+			let end = after
+				.find('}')
+				.ok_or_else(|| format!("Unterminated variable in '{}'", value))?;
+			let name = &after[..end];
+			let substitute = match name.strip_prefix("env:") {
+				Some(name) => {
+					env(name).ok_or_else(|| format!("Undefined environment variable '{}'", name))?
+				}
+				None => vars
+					.get(name)
+					.cloned()
+					.ok_or_else(|| format!("Undefined variable '{}'", name))?,
+			};
+			interpolated.push_str(&substitute);
+			rest = &after[end + 1..];
+		} else {
+			interpolated.push('$');
+			rest = &rest[1..];
+		}
+	}
+	interpolated.push_str(rest);
+	Ok(interpolated)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Builder, JsonSchema)]
 #[builder(setter(into))]
+#[serde(deny_unknown_fields)]
 pub struct Layout {
+	/// The version of the layout format the layout is written in, the latest if not given.
+	#[builder(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub version: Option<u32>,
+
+	/// The name of a layout to inherit screens and rules from.
+	#[builder(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub extends: Option<String>,
+
+	/// The names of layouts whose screens and rules are merged in, in order, after `extends`.
 	#[builder(default)]
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub include: Vec<String>,
+
+	/// Variables substituted for `${name}` in the rules, see `interpolate`.
+	#[builder(default)]
+	#[serde(
+		default,
+		deserialize_with = "vars",
+		skip_serializing_if = "BTreeMap::is_empty"
+	)]
+	#[schemars(schema_with = "vars_schema")]
+	pub vars: BTreeMap<String, String>,
+
+	/// The displays the layout is meant for, which `wlm layout --auto` picks the layout by.
+	#[builder(default)]
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub match_displays: Vec<DisplayMatcher>,
+
+	/// The screens (monitors) of the layout and the rules for the windows on them.
+	#[builder(default)]
+	#[serde(default)]
 	pub screens: Vec<Screen>,
 }
 
 impl Layout {
 	pub fn new() -> Self {
 		Self {
+			version: None,
+			extends: None,
+			include: Vec::new(),
+			vars: BTreeMap::new(),
+			match_displays: Vec::new(),
 			screens: Vec::new(),
 		}
 	}
+
+	/// Parses a layout written in `format`.
+	///
+	/// A config of wlm 0.3 is converted into a layout, see `migrate::parse_legacy`. Inherited
+	/// layouts are not resolved, see `Layout::resolve`.
+	///
+	/// # Errors
+	///
+	/// Returns an error, located where the parser reports it, if `content` is not a valid layout.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use wlm::layout::{Format, Layout};
+	/// let layout = Layout::parse("screens:\n- windows:\n  - w: 940\n", Format::Yaml).unwrap();
+	/// assert_eq!(Some("940".to_string()), layout.screens[0].windows[0].w);
+	///
+	/// let err = Layout::parse("screens:\n- windows:\n  - titel: Inbox\n", Format::Yaml).unwrap_err();
+	/// assert_eq!(Some((3, 5)), err.location);
+	/// ```
+	pub fn parse(content: &str, format: Format) -> Result<Layout, ParseError> {
+		let layout = match Layout::parse_current(content, format) {
+			Ok(layout) => layout,
+			Err(e) => match crate::migrate::parse_legacy(content, format) {
+				Some(layout) => {
+					log::warn!("Converting a wlm 0.3 config, run `wlm migrate` to update it");
+					layout
+				}
+				None => return Err(e),
+			},
+		};
+		match layout.version {
+			Some(version) if version > LAYOUT_VERSION => Err(ParseError::new(
+				format!(
+					"Layout version {} is not supported, the latest is {}",
+					version, LAYOUT_VERSION
+				),
+				None,
+			)),
+			_ => Ok(layout),
+		}
+	}
+
+	fn parse_current(content: &str, format: Format) -> Result<Layout, ParseError> {
+		match format {
+			Format::Yaml => serde_yaml::from_str(content).map_err(|e| {
+				let location = e.location().map(|l| (l.line(), l.column()));
+				ParseError::new(e.to_string(), location)
+			}),
+			Format::Json => serde_json::from_str(content).map_err(|e| {
+				let location = Some((e.line(), e.column())).filter(|(line, _)| *line > 0);
+				ParseError::new(e.to_string(), location)
+			}),
+			Format::Toml => toml::from_str(content).map_err(|e| {
+				let location = e.line_col().map(|(line, column)| (line + 1, column + 1));
+				ParseError::new(e.to_string(), location)
+			}),
+			Format::Ron => ron::Options::default()
+				.with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME)
+				.from_str(content)
+				.map_err(|e| {
+					ParseError::new(e.code.to_string(), Some((e.position.line, e.position.col)))
+				}),
+			Format::Table | Format::Ndjson | Format::Csv | Format::Tsv => Err(ParseError::new(
+				format!("Layouts cannot be read as {}", format),
+				None,
+			)),
+		}
+	}
+
+	/// Serializes the layout as `format`.
+	///
+	/// # Errors
+	///
+	/// Returns an error if `format` is not a layout format or the serialization fails.
+	pub fn serialize(&self, format: Format) -> Result<String, String> {
+		match format {
+			format if format.extension().is_none() => {
+				Err(format!("Layouts cannot be written as {}", format))
+			}
+			format => format.serialize(self),
+		}
+	}
+
+	/// Returns the JSON Schema describing layout files, for editors to complete and check them.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use wlm::layout::Layout;
+	/// let schema: serde_json::Value = serde_json::from_str(&Layout::schema()).unwrap();
+	/// assert_eq!("Layout", schema["title"]);
+	/// ```
+	pub fn schema() -> String {
+		serde_json::to_string_pretty(&schema_for!(Layout)).unwrap() + "\n"
+	}
+
+	/// Merges the screens of `other` on top of the screens of this layout.
+	///
+	/// Screens are matched by `id` and `when` and their rules merged, see `Screen::merge`.
+	/// Screens not already in this layout are appended. Variables of `other` override those of the same name.
+	pub fn merge(&mut self, other: Layout) {
+		self.vars.extend(other.vars);
+		for screen in other.screens {
+			let existing = self
+				.screens
+				.iter_mut()
+				.find(|s| s.id == screen.id && s.when == screen.when);
+			match existing {
+				Some(existing) => existing.merge(screen),
+				None => self.screens.push(screen),
+			}
+		}
+	}
+
+	/// Resolves `extends` and `include` into a single layout, using `load` to load other layouts
+	/// by name.
+	///
+	/// The `extends` layout is merged first, then each `include` in order, then the screens of
+	/// this layout, so later sources override earlier ones. Inherited layouts are resolved
+	/// recursively.
+	///
+	/// # Errors
+	///
+	/// Returns an error if `load` fails or if the layouts inherit from each other in a cycle.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use wlm::layout::{Layout, LayoutBuilder, ScreenBuilder, WindowBuilder};
+	/// let rule = WindowBuilder::default().process(Some("slack".to_string())).build().unwrap();
+	/// let base = LayoutBuilder::default()
+	///     .screens(vec![ScreenBuilder::default().windows(vec![rule]).build().unwrap()])
+	///     .build()
+	///     .unwrap();
+	/// let office = LayoutBuilder::default().extends(Some("base".to_string())).build().unwrap();
+	/// let office = office.resolve(|_| Ok(base.clone())).unwrap();
+	/// assert_eq!(1, office.screens[0].windows.len());
+	/// ```
+	pub fn resolve<F>(self, mut load: F) -> Result<Layout, String>
+	where
+		F: FnMut(&str) -> Result<Layout, String>,
+	{
+		self.resolve_with(&mut load, &mut Vec::new())
+	}
+
+	fn resolve_with(
+		self,
+		load: &mut dyn FnMut(&str) -> Result<Layout, String>,
+		chain: &mut Vec<String>,
+	) -> Result<Layout, String> {
+		let mut resolved = Layout::new();
+		for name in self.extends.iter().chain(self.include.iter()) {
+			if chain.contains(name) {
+				return Err(format!(
+					"Layout inheritance cycle: {} -> {}",
+					chain.join(" -> "),
+					name
+				));
+			}
+			chain.push(name.clone());
+			let parent = load(name)?.resolve_with(load, chain)?;
+			chain.pop();
+			resolved.merge(parent);
+		}
+		resolved.match_displays = self.match_displays;
+		resolved.merge(Layout {
+			vars: self.vars,
+			screens: self.screens,
+			..Layout::new()
+		});
+		Ok(resolved)
+	}
+
+	/// Keeps only the screens and rules whose `when` condition holds in `context`, dropping
+	/// the conditions of those kept.
+	///
+	/// # Errors
+	///
+	/// Returns an error if a condition is not valid.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use wlm::condition::{Context, WhenBuilder};
+	/// # use wlm::layout::{LayoutBuilder, ScreenBuilder, WindowBuilder};
+	/// let docked = WhenBuilder::default().monitors(Some("3".to_string())).build().unwrap();
+	/// let rule = WindowBuilder::default().when(Some(docked)).build().unwrap();
+	/// let screen = ScreenBuilder::default().windows(vec![rule]).build().unwrap();
+	/// let layout = LayoutBuilder::default().screens(vec![screen]).build().unwrap();
+	/// let laptop = Context::new("laptop", "linux", vec![ScreenBuilder::default().build().unwrap()]);
+	/// assert!(layout.select(&laptop).unwrap().screens[0].windows.is_empty());
+	/// ```
+	pub fn select(mut self, context: &Context) -> Result<Layout, String> {
+		let holds = |when: &Option<When>, id: Option<u8>| match when {
+			Some(when) => when.matches(context, id),
+			None => Ok(true),
+		};
+		let mut screens = Vec::new();
+		for mut screen in self.screens {
+			if !holds(&screen.when, screen.id)? {
+				continue;
+			}
+			let mut windows = Vec::new();
+			for mut window in screen.windows {
+				if holds(&window.when, screen.id)? {
+					window.when = None;
+					windows.push(window);
+				}
+			}
+			screen.when = None;
+			screen.windows = windows;
+			screens.push(screen);
+		}
+		self.screens = screens;
+		Ok(self)
+	}
+
+	/// Substitutes the variables of this layout and the environment variables into the rules,
+	/// see `interpolate`.
+	///
+	/// # Errors
+	///
+	/// Returns an error if a rule refers to an undefined variable.
+	pub fn interpolate(self) -> Result<Layout, String> {
+		self.interpolate_with(|name| std::env::var(name).ok())
+	}
+
+	/// Substitutes the variables of this layout into the rules, looking up environment variables
+	/// with `env`.
+	///
+	/// A desktop name that turns into a number addresses the desktop by index.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use wlm::layout::{Layout, LayoutBuilder, ScreenBuilder, WindowBuilder};
+	/// let rule = WindowBuilder::default()
+	///     .title(Some("${env:PROJECT} - Visual Studio Code".to_string()))
+	///     .w(Some("${sidebar}".to_string()))
+	///     .build()
+	///     .unwrap();
+	/// let layout = LayoutBuilder::default()
+	///     .vars([("sidebar".to_string(), "30%".to_string())])
+	///     .screens(vec![ScreenBuilder::default().windows(vec![rule]).build().unwrap()])
+	///     .build()
+	///     .unwrap();
+	/// let layout = layout.interpolate_with(|_| Some("wlm".to_string())).unwrap();
+	/// let rule = &layout.screens[0].windows[0];
+	/// assert_eq!(Some("wlm - Visual Studio Code".to_string()), rule.title);
+	/// assert_eq!(Some("30%".to_string()), rule.w);
+	/// ```
+	pub fn interpolate_with<F>(mut self, env: F) -> Result<Layout, String>
+	where
+		F: Fn(&str) -> Option<String>,
+	{
+		for window in self.screens.iter_mut().flat_map(|s| s.windows.iter_mut()) {
+			for value in [
+				&mut window.name,
+				&mut window.title,
+				&mut window.process,
+				&mut window.x,
+				&mut window.y,
+				&mut window.w,
+				&mut window.h,
+			]
+			.into_iter()
+			.flatten()
+			{
+				*value = interpolate(value, &self.vars, &env)?;
+			}
+			if let Some(Desktop::Name(name)) = &window.desktop {
+				let name = interpolate(name, &self.vars, &env)?;
+				window.desktop = Some(match name.parse::<u32>() {
+					Ok(index) => Desktop::Index(index),
+					Err(_) => Desktop::Name(name),
+				});
+			}
+		}
+		Ok(self)
+	}
+
+	/// Marks the rule named `name` as the window to focus once the layout is applied, clearing
+	/// `focus` from every other rule.
+	///
+	/// # Errors
+	///
+	/// Returns an error if no rule is named `name`.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use wlm::layout::{LayoutBuilder, ScreenBuilder, WindowBuilder};
+	/// let editor = WindowBuilder::default().name(Some("editor".to_string())).build().unwrap();
+	/// let screen = ScreenBuilder::default().windows(vec![editor]).build().unwrap();
+	/// let mut layout = LayoutBuilder::default().screens(vec![screen]).build().unwrap();
+	/// assert!(layout.focus("editor").is_ok());
+	/// assert_eq!(Some(true), layout.screens[0].windows[0].focus);
+	/// assert!(layout.focus("terminal").is_err());
+	/// ```
+	pub fn focus<S: AsRef<str>>(&mut self, name: S) -> Result<(), String> {
+		let name = name.as_ref();
+		if !self
+			.screens
+			.iter()
+			.flat_map(|s| &s.windows)
+			.any(|w| w.name.as_deref() == Some(name))
+		{
+			return Err(format!("No rule named '{name}'"));
+		}
+		for window in self.screens.iter_mut().flat_map(|s| s.windows.iter_mut()) {
+			window.focus = if window.name.as_deref() == Some(name) {
+				Some(true)
+			} else {
+				None
+			};
+		}
+		Ok(())
+	}
+
+	/// Appends `rule` to the screen `id`, adding the screen if the layout has none without a
+	/// `when` condition.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use wlm::layout::{Layout, WindowBuilder};
+	/// let mut layout = Layout::new();
+	/// let rule = WindowBuilder::default().process(Some("code".to_string())).build().unwrap();
+	/// layout.add_rule(Some(1), rule.clone());
+	/// layout.add_rule(Some(1), rule);
+	/// assert_eq!(1, layout.screens.len());
+	/// assert_eq!(2, layout.screens[0].windows.len());
+	/// ```
+	pub fn add_rule(&mut self, id: Option<u8>, rule: Window) {
+		match self
+			.screens
+			.iter_mut()
+			.find(|s| s.id == id && s.when.is_none())
+		{
+			Some(screen) => screen.windows.push(rule),
+			None => self.screens.push(Screen {
+				id,
+				windows: vec![rule],
+				..Screen::new()
+			}),
+		}
+	}
 }
 
 impl Default for Layout {
@@ -60,9 +966,15 @@ impl Default for Layout {
 /// This struct allows for optional customization of various window properties such as size, coordinates,
 /// process name, and window state (maximized, minimized, etc.). Each field is optional, allowing for
 /// flexibility in specifying only the desired attributes.
-#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Builder)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Builder, JsonSchema)]
 #[builder(setter(into))]
+#[serde(deny_unknown_fields)]
 pub struct Window {
+	/// A name identifying the rule, e.g. to refer to it from the command line.
+	#[builder(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub name: Option<String>,
+
 	/// The title of the window. This is an optional field, and if not provided, a default
 	/// value may be used depending on the context.
 	#[builder(default)]
@@ -77,12 +989,22 @@ pub struct Window {
 
 	/// The top left x-coordinate of the window's position.
 	#[builder(default)]
-	#[serde(skip_serializing_if = "Option::is_none")]
+	#[serde(
+		default,
+		deserialize_with = "string_or_number",
+		skip_serializing_if = "Option::is_none"
+	)]
+	#[schemars(schema_with = "length_schema")]
 	pub x: Option<String>,
 
 	/// The top left y-coordinate of the window's position.
 	#[builder(default)]
-	#[serde(skip_serializing_if = "Option::is_none")]
+	#[serde(
+		default,
+		deserialize_with = "string_or_number",
+		skip_serializing_if = "Option::is_none"
+	)]
+	#[schemars(schema_with = "length_schema")]
 	pub y: Option<String>,
 
 	/// The z-order of the window, which determines its stacking order relative to other windows.
@@ -108,13 +1030,23 @@ pub struct Window {
 	///     w: '40%'
 	/// ```
 	#[builder(default)]
-	#[serde(skip_serializing_if = "Option::is_none")]
+	#[serde(
+		default,
+		deserialize_with = "string_or_number",
+		skip_serializing_if = "Option::is_none"
+	)]
+	#[schemars(schema_with = "length_schema")]
 	pub w: Option<String>,
 
 	/// The height of the window. Similar to width, this is optional and a default value
 	/// may be used if not provided.
 	#[builder(default)]
-	#[serde(skip_serializing_if = "Option::is_none")]
+	#[serde(
+		default,
+		deserialize_with = "string_or_number",
+		skip_serializing_if = "Option::is_none"
+	)]
+	#[schemars(schema_with = "length_schema")]
 	pub h: Option<String>,
 
 	/// A flag indicating whether the window is maximized. This is optional and defaults to
@@ -140,12 +1072,188 @@ pub struct Window {
 	#[builder(default)]
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub minimized: Option<bool>,
+
+	/// The virtual desktop (workspace) the window is moved to, addressed by index or name.
+	///
+	/// # Examples
+	/// ```yaml
+	/// screens:
+	///   windows:
+	///   - process: 'slack'
+	///     desktop: 3
+	///   - process: 'thunderbird'
+	///     desktop: 'comms'
+	/// ```
+	#[builder(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub desktop: Option<Desktop>,
+
+	/// A flag indicating whether the window is shown on all desktops. Takes precedence over
+	/// `desktop` when both are specified.
+	#[builder(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub sticky: Option<bool>,
+
+	/// A flag indicating whether the window is raised and activated once the layout is applied.
+	/// When several rules set it, the window matched by the last of them ends up focused.
+	#[builder(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub focus: Option<bool>,
+
+	/// A condition the rule is only applied under, e.g. on a given host or monitor setup.
+	#[builder(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub when: Option<When>,
+
+	/// The id the window system knows the window by, e.g. its X11 window id or `HWND`, as
+	/// reported when listing windows. Ignored in a layout file, but set on the rules undoing a
+	/// layout to move back the same windows, see `Window::matches`.
+	#[builder(default)]
+	#[serde(
+		skip_serializing_if = "Option::is_none",
+		default,
+		deserialize_with = "ignored"
+	)]
+	#[schemars(skip)]
+	pub id: Option<u64>,
+
+	/// The id of the process the window belongs to, as reported when listing windows. Ignored in
+	/// a layout file.
+	#[builder(default)]
+	#[serde(
+		skip_serializing_if = "Option::is_none",
+		default,
+		deserialize_with = "ignored"
+	)]
+	#[schemars(skip)]
+	pub pid: Option<u32>,
 }
 
 impl Window {
 	pub fn new() -> Self {
 		Window::default()
 	}
+
+	/// Overrides the fields of this window with every field specified by `other`.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use wlm::layout::WindowBuilder;
+	/// let mut base = WindowBuilder::default()
+	///     .process(Some("code".to_string()))
+	///     .w(Some("50%".to_string()))
+	///     .build()
+	///     .unwrap();
+	/// base.merge(WindowBuilder::default().w(Some("70%".to_string())).build().unwrap());
+	/// assert_eq!(Some("code".to_string()), base.process);
+	/// assert_eq!(Some("70%".to_string()), base.w);
+	/// ```
+	pub fn merge(&mut self, other: Window) {
+		macro_rules! merge {
+			($($field:ident),*) => {
+				$(
+					if other.$field.is_some() {
+						self.$field = other.$field;
+					}
+				)*
+			};
+		}
+		merge!(
+			name,
+			title,
+			process,
+			x,
+			y,
+			z,
+			w,
+			h,
+			maximized,
+			maximized_vertical,
+			maximized_horizontal,
+			minimized,
+			desktop,
+			sticky,
+			focus,
+			when
+		);
+	}
+
+	/// Returns whether `other` is matched by the `title` and `process` regular expressions of
+	/// this window.
+	///
+	/// When both are specified both must match. A window without either never matches anything,
+	/// nor does one with an invalid regular expression. A rule with an `id`, as recorded to undo
+	/// a layout, only matches the window with that id.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use wlm::layout::WindowBuilder;
+	/// let rule = WindowBuilder::default().process(Some("firefox".to_string())).build().unwrap();
+	/// let live = WindowBuilder::default()
+	///     .title(Some("Mozilla Firefox".to_string()))
+	///     .process(Some("/usr/lib/firefox/firefox".to_string()))
+	///     .build()
+	///     .unwrap();
+	/// assert!(rule.matches(&live));
+	/// ```
+	pub fn matches(&self, other: &Window) -> bool {
+		if let Some(id) = self.id {
+			return other.id == Some(id);
+		}
+		fn is_match(pattern: &str, value: &Option<String>) -> bool {
+			match (Regex::new(pattern), value) {
+				(Ok(regex), Some(value)) => regex.is_match(value),
+				_ => false,
+			}
+		}
+		match (&self.title, &self.process) {
+			(Some(title), Some(process)) => {
+				is_match(title, &other.title) && is_match(process, &other.process)
+			}
+			(Some(title), None) => is_match(title, &other.title),
+			(None, Some(process)) => is_match(process, &other.process),
+			(None, None) => false,
+		}
+	}
+
+	/// Returns this window with its position and size in pixels converted into percentages of
+	/// `bounds`, the monitor it is on, as they are resolved when applying a layout: positions
+	/// from the top left corner of the monitor.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use wlm::layout::{Rect, WindowBuilder};
+	/// let window = WindowBuilder::default()
+	///     .x(Some("2560".to_string()))
+	///     .w(Some("1280".to_string()))
+	///     .h(Some("50%".to_string()))
+	///     .build()
+	///     .unwrap();
+	/// let window = window.relative_to(&Rect::new(2560, 0, 2560, 1440));
+	/// assert_eq!(Some("0%".to_string()), window.x);
+	/// assert_eq!(Some("50%".to_string()), window.w);
+	/// assert_eq!(Some("50%".to_string()), window.h);
+	/// ```
+	pub fn relative_to(self, bounds: &Rect) -> Window {
+		let relative = |value: Option<String>, origin: i32, extent: i32| {
+			value.map(|value| match value.parse::<Length>() {
+				Ok(Length::Pixels(pixels)) => {
+					Length::percent_of(pixels - origin, extent).to_string()
+				}
+				_ => value,
+			})
+		};
+		Window {
+			x: relative(self.x, bounds.x, bounds.w),
+			y: relative(self.y, bounds.y, bounds.h),
+			w: relative(self.w, 0, bounds.w),
+			h: relative(self.h, 0, bounds.h),
+			..self
+		}
+	}
 }
 
 impl std::fmt::Display for Window {
@@ -154,13 +1262,70 @@ impl std::fmt::Display for Window {
 	}
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Builder)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Builder, JsonSchema)]
 #[builder(setter(into))]
+#[serde(deny_unknown_fields)]
 pub struct Screen {
+	/// The zero-based index of the monitor the rules apply to.
 	#[builder(default)]
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub id: Option<u8>,
+
+	/// The bounds of the monitor, as reported when listing windows. Ignored when applying a
+	/// layout.
+	#[builder(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub bounds: Option<Rect>,
+
+	/// The connector name of the monitor, e.g. `eDP-1`, as reported when listing windows.
+	#[builder(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub name: Option<String>,
+
+	/// The EDID fingerprint of the monitor, see `fingerprint::edid_fingerprint`, as reported
+	/// when listing windows.
+	#[builder(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub edid: Option<String>,
+
+	/// The part of the monitor windows are maximized to, leaving out panels and task bars, as
+	/// reported when listing windows.
+	#[builder(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub work_area: Option<Rect>,
+
+	/// Whether the monitor is the primary one, as reported when listing windows.
+	#[builder(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub primary: Option<bool>,
+
+	/// The dots per inch of the monitor, as reported when listing windows: the physical density
+	/// on X11, the density windows are scaled for on Windows.
+	#[builder(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub dpi: Option<u32>,
+
+	/// The factor windows are scaled by on the monitor, e.g. `1.5`, as reported when listing
+	/// windows.
+	#[builder(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub scale: Option<f64>,
+
+	/// The clockwise rotation of the monitor in degrees, one of 0, 90, 180 and 270, as reported
+	/// when listing windows.
+	#[builder(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub rotation: Option<u32>,
+
+	/// A condition the screen and its rules are only applied under, e.g. on a given host or
+	/// monitor setup.
+	#[builder(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub when: Option<When>,
+
+	/// The rules matching windows and where to move them.
 	#[builder(default)]
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
 	pub windows: Vec<Window>,
 }
 
@@ -168,9 +1333,42 @@ impl Screen {
 	pub fn new() -> Self {
 		Screen {
 			id: None,
+			bounds: None,
+			name: None,
+			edid: None,
+			work_area: None,
+			primary: None,
+			dpi: None,
+			scale: None,
+			rotation: None,
+			when: None,
 			windows: Vec::new(),
 		}
 	}
+
+	/// Merges the rules of `other` into this screen.
+	///
+	/// A named rule overrides the rule of the same name already in this screen, see
+	/// `Window::merge`. Every other rule is appended.
+	pub fn merge(&mut self, other: Screen) {
+		for rule in other.windows {
+			let existing = rule.name.as_ref().and_then(|name| {
+				self.windows
+					.iter_mut()
+					.find(|w| w.name.as_ref() == Some(name))
+			});
+			match existing {
+				Some(existing) => existing.merge(rule),
+				None => self.windows.push(rule),
+			}
+		}
+	}
+}
+
+impl Default for Screen {
+	fn default() -> Self {
+		Screen::new()
+	}
 }
 
 impl std::fmt::Display for Screen {
@@ -178,3 +1376,389 @@ impl std::fmt::Display for Screen {
 		write!(f, "{:?}", self)
 	}
 }
+
+#[cfg(test)]
+mod test_window {
+	use super::*;
+
+	fn live(title: &str, process: &str) -> Window {
+		WindowBuilder::default()
+			.title(Some(title.to_string()))
+			.process(Some(process.to_string()))
+			.build()
+			.unwrap()
+	}
+
+	#[test]
+	fn matches_title_and_process() {
+		let rule = WindowBuilder::default()
+			.title(Some("Inbox".to_string()))
+			.process(Some("thunderbird".to_string()))
+			.build()
+			.unwrap();
+		assert!(rule.matches(&live("Inbox - Mozilla Thunderbird", "thunderbird")));
+		assert!(!rule.matches(&live("Inbox - Mozilla Thunderbird", "firefox")));
+	}
+
+	#[test]
+	fn rule_without_selectors_matches_nothing() {
+		assert!(!Window::new().matches(&live("Inbox", "thunderbird")));
+	}
+
+	#[test]
+	fn invalid_regex_matches_nothing() {
+		let rule = WindowBuilder::default()
+			.title(Some("(".to_string()))
+			.build()
+			.unwrap();
+		assert!(!rule.matches(&live("(", "thunderbird")));
+	}
+
+	#[test]
+	fn deserialize_desktop_by_index() {
+		let window: Window = serde_yaml::from_str("desktop: 3").unwrap();
+		assert_eq!(Some(Desktop::Index(3)), window.desktop);
+	}
+
+	#[test]
+	fn deserialize_desktop_by_name() {
+		let window: Window = serde_yaml::from_str("desktop: comms").unwrap();
+		assert_eq!(Some(Desktop::Name("comms".to_string())), window.desktop);
+	}
+}
+
+#[cfg(test)]
+mod test_layout {
+	use super::*;
+
+	fn rule(name: Option<&str>, process: &str, w: &str) -> Window {
+		WindowBuilder::default()
+			.name(name.map(String::from))
+			.process(Some(process.to_string()))
+			.w(Some(w.to_string()))
+			.build()
+			.unwrap()
+	}
+
+	fn layout(extends: Option<&str>, include: &[&str], screens: Vec<Screen>) -> Layout {
+		LayoutBuilder::default()
+			.extends(extends.map(String::from))
+			.include(
+				include
+					.iter()
+					.map(|s| s.to_string())
+					.collect::<Vec<String>>(),
+			)
+			.screens(screens)
+			.build()
+			.unwrap()
+	}
+
+	fn screen(id: u8, windows: Vec<Window>) -> Screen {
+		ScreenBuilder::default()
+			.id(id)
+			.windows(windows)
+			.build()
+			.unwrap()
+	}
+
+	fn load(name: &str) -> Result<Layout, String> {
+		match name {
+			"base" => Ok(layout(
+				None,
+				&[],
+				vec![screen(
+					0,
+					vec![
+						rule(Some("editor"), "code", "50%"),
+						rule(None, "firefox", "50%"),
+					],
+				)],
+			)),
+			"comms" => Ok(layout(
+				None,
+				&[],
+				vec![screen(1, vec![rule(Some("chat"), "slack", "30%")])],
+			)),
+			"cycle" => Ok(layout(Some("cycle"), &[], vec![])),
+			_ => Err(format!("No layout named '{name}'")),
+		}
+	}
+
+	fn office() -> Layout {
+		layout(
+			Some("base"),
+			&["comms"],
+			vec![screen(0, vec![rule(Some("editor"), "code", "70%")])],
+		)
+	}
+
+	#[test]
+	fn serialize_yaml_round_trip() {
+		let yaml = office().serialize(Format::Yaml).unwrap();
+		assert_eq!(office(), serde_yaml::from_str(&yaml).unwrap());
+	}
+
+	#[test]
+	fn serialize_json_round_trip() {
+		let json = office().serialize(Format::Json).unwrap();
+		assert_eq!(office(), serde_json::from_str(&json).unwrap());
+	}
+
+	#[test]
+	fn serialize_toml_round_trip() {
+		let toml = office().serialize(Format::Toml).unwrap();
+		assert_eq!(office(), toml::from_str(&toml).unwrap());
+	}
+
+	#[test]
+	fn serialize_ron_round_trip() {
+		let ron = office().serialize(Format::Ron).unwrap();
+		assert_eq!(office(), ron::from_str(&ron).unwrap());
+	}
+
+	#[test]
+	fn serialize_table() {
+		assert!(office().serialize(Format::Table).is_err());
+	}
+
+	#[test]
+	fn resolve_without_inheritance() {
+		let office = layout(None, &[], vec![screen(0, vec![rule(None, "code", "50%")])]);
+		assert_eq!(
+			office.screens,
+			office.clone().resolve(load).unwrap().screens
+		);
+	}
+
+	#[test]
+	fn resolve_extends() {
+		let office = layout(Some("base"), &[], vec![]);
+		let office = office.resolve(load).unwrap();
+		assert_eq!(None, office.extends);
+		assert_eq!(load("base").unwrap().screens, office.screens);
+	}
+
+	#[test]
+	fn resolve_overrides_named_rule() {
+		let office = layout(
+			Some("base"),
+			&[],
+			vec![screen(0, vec![rule(Some("editor"), "code", "70%")])],
+		);
+		let office = office.resolve(load).unwrap();
+		assert_eq!(2, office.screens[0].windows.len());
+		assert_eq!(Some("70%".to_string()), office.screens[0].windows[0].w);
+	}
+
+	#[test]
+	fn resolve_appends_unnamed_rule() {
+		let office = layout(
+			Some("base"),
+			&[],
+			vec![screen(0, vec![rule(None, "firefox", "30%")])],
+		);
+		let office = office.resolve(load).unwrap();
+		assert_eq!(3, office.screens[0].windows.len());
+		assert_eq!(Some("30%".to_string()), office.screens[0].windows[2].w);
+	}
+
+	#[test]
+	fn resolve_include_after_extends() {
+		let office = layout(Some("base"), &["comms"], vec![]);
+		let office = office.resolve(load).unwrap();
+		assert_eq!(2, office.screens.len());
+		assert_eq!(Some(1), office.screens[1].id);
+	}
+
+	#[test]
+	fn resolve_missing_layout() {
+		assert!(layout(Some("missing"), &[], vec![]).resolve(load).is_err());
+	}
+
+	#[test]
+	fn resolve_cycle() {
+		let err = layout(Some("cycle"), &[], vec![])
+			.resolve(load)
+			.unwrap_err();
+		assert_eq!("Layout inheritance cycle: cycle -> cycle", err);
+	}
+
+	#[test]
+	fn parse_numeric_lengths() {
+		let layout = Layout::parse(
+			"[[screens]]\n[[screens.windows]]\nprocess = \"code\"\nw = 50\n",
+			Format::Toml,
+		)
+		.unwrap();
+		assert_eq!(Some("50".to_string()), layout.screens[0].windows[0].w);
+	}
+
+	#[test]
+	fn parse_ron_without_some() {
+		let ron = "(screens: [(windows: [(process: \"code\", w: \"50%\")])])";
+		let layout = Layout::parse(ron, Format::Ron).unwrap();
+		assert_eq!(Some("50%".to_string()), layout.screens[0].windows[0].w);
+	}
+
+	#[test]
+	fn parse_error_is_located() {
+		let e =
+			Layout::parse("screens:\n- windows:\n  - titel: Inbox\n", Format::Yaml).unwrap_err();
+		assert_eq!(Some((3, 5)), e.location);
+		assert!(e.message.contains("unknown field `titel`"));
+	}
+
+	#[test]
+	fn schema_describes_window_fields() {
+		let schema: serde_json::Value = serde_json::from_str(&Layout::schema()).unwrap();
+		let window = &schema["definitions"]["Window"];
+		assert_eq!(false, window["additionalProperties"]);
+		assert!(window["properties"]["sticky"]["description"]
+			.as_str()
+			.unwrap()
+			.starts_with("A flag indicating whether the window is shown on all desktops."));
+	}
+
+	#[test]
+	fn listed_ids_are_ignored_in_a_layout() {
+		let schema: serde_json::Value = serde_json::from_str(&Layout::schema()).unwrap();
+		let properties = &schema["definitions"]["Window"]["properties"];
+		assert!(properties.get("id").is_none());
+		assert!(properties.get("pid").is_none());
+		let listed = Layout::parse(
+			"screens:\n- windows:\n  - id: 7\n    pid: 42\n",
+			Format::Yaml,
+		);
+		assert_eq!(None, listed.unwrap().screens[0].windows[0].pid);
+		let mut rule = rule(None, "code", "50%");
+		rule.merge(WindowBuilder::default().id(Some(7)).build().unwrap());
+		assert_eq!(None, rule.id);
+	}
+
+	#[test]
+	fn schema_accepts_lengths() {
+		let schema: serde_json::Value = serde_json::from_str(&Layout::schema()).unwrap();
+		let pattern = schema["definitions"]["Window"]["properties"]["w"]["pattern"]
+			.as_str()
+			.unwrap();
+		let pattern = Regex::new(pattern).unwrap();
+		for length in ["940", "940px", "-8", "40%", "12.5%"] {
+			assert!(pattern.is_match(length), "{}", length);
+		}
+		assert!(!pattern.is_match("wide"));
+	}
+
+	#[test]
+	fn parse_vars_of_numbers() {
+		let layout = Layout::parse("vars:\n  sidebar: 400\n  ratio: 0.5\n", Format::Yaml).unwrap();
+		assert_eq!(Some(&"400".to_string()), layout.vars.get("sidebar"));
+		assert_eq!(Some(&"0.5".to_string()), layout.vars.get("ratio"));
+	}
+
+	#[test]
+	fn resolve_overrides_inherited_vars() {
+		let base = LayoutBuilder::default()
+			.vars([
+				("sidebar".to_string(), "30%".to_string()),
+				("editor".to_string(), "code".to_string()),
+			])
+			.build()
+			.unwrap();
+		let office = LayoutBuilder::default()
+			.extends(Some("base".to_string()))
+			.vars([("sidebar".to_string(), "25%".to_string())])
+			.screens(vec![screen(0, vec![rule(None, "${editor}", "${sidebar}")])])
+			.build()
+			.unwrap();
+		let office = office
+			.resolve(|_| Ok(base.clone()))
+			.unwrap()
+			.interpolate_with(|_| None)
+			.unwrap();
+		let rule = &office.screens[0].windows[0];
+		assert_eq!(Some("code".to_string()), rule.process);
+		assert_eq!(Some("25%".to_string()), rule.w);
+	}
+
+	#[test]
+	fn interpolate_desktop_index() {
+		let rule = WindowBuilder::default()
+			.desktop(Some(Desktop::from("${comms}")))
+			.build()
+			.unwrap();
+		let layout = LayoutBuilder::default()
+			.vars([("comms".to_string(), "3".to_string())])
+			.screens(vec![screen(0, vec![rule])])
+			.build()
+			.unwrap()
+			.interpolate_with(|_| None)
+			.unwrap();
+		assert_eq!(
+			Some(Desktop::Index(3)),
+			layout.screens[0].windows[0].desktop
+		);
+	}
+
+	#[test]
+	fn interpolate_undefined() {
+		let layout = layout(None, &[], vec![screen(0, vec![rule(None, "code", "${w}")])]);
+		assert_eq!(
+			Err("Undefined variable 'w'".to_string()),
+			layout.clone().interpolate_with(|_| None)
+		);
+		let layout = layout
+			.screens
+			.into_iter()
+			.map(|mut s| {
+				s.windows[0].w = Some("${unterminated".to_string());
+				s
+			})
+			.collect::<Vec<Screen>>();
+		let layout = LayoutBuilder::default().screens(layout).build().unwrap();
+		assert_eq!(
+			Err("Unterminated variable in '${unterminated'".to_string()),
+			layout.interpolate_with(|_| None)
+		);
+	}
+
+	#[test]
+	fn select_by_monitor_count() {
+		let when = |monitors: &str| {
+			Some(When {
+				monitors: Some(monitors.to_string()),
+				..When::default()
+			})
+		};
+		let mut laptop = screen(0, vec![rule(None, "code", "100%")]);
+		laptop.when = when("1");
+		let mut docked = screen(0, vec![rule(None, "code", "50%")]);
+		docked.when = when(">=2");
+		let layout = layout(None, &[], vec![laptop, docked])
+			.resolve(|_| Err("No inheritance".to_string()))
+			.unwrap();
+		assert_eq!(2, layout.screens.len());
+
+		let context = Context::new("laptop", "linux", vec![Screen::new(), Screen::new()]);
+		let layout = layout.select(&context).unwrap();
+		assert_eq!(1, layout.screens.len());
+		assert_eq!(None, layout.screens[0].when);
+		assert_eq!(Some("50%".to_string()), layout.screens[0].windows[0].w);
+	}
+
+	#[test]
+	fn parse_legacy_config() {
+		let legacy = r#"{"windows": [{"process": "code", "w": 960}]}"#;
+		let layout = Layout::parse(legacy, Format::Json).unwrap();
+		assert_eq!(Some("960".to_string()), layout.screens[0].windows[0].w);
+	}
+
+	#[test]
+	fn parse_newer_version() {
+		let e = Layout::parse("version: 2\nscreens: []\n", Format::Yaml).unwrap_err();
+		assert_eq!(
+			"Layout version 2 is not supported, the latest is 1",
+			e.message
+		);
+	}
+}
@@ -1,9 +1,23 @@
 #[macro_use]
 extern crate derive_builder;
 #[macro_use]
+extern crate schemars;
+#[macro_use]
 extern crate serde;
 
+pub mod condition;
+pub mod config;
+pub mod diff;
+pub mod export;
+pub mod fingerprint;
+pub mod import;
+pub mod journal;
 pub mod layout;
+pub mod library;
+pub mod listing;
+pub mod migrate;
+pub mod snapshot;
+pub mod validate;
 
 #[cfg(windows)]
 #[path = "platform/mod.rs"]
@@ -13,6 +27,9 @@ pub mod platform;
 #[path = "platform/mod.rs"]
 pub mod platform;
 
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
 static ELLIPSIS: &str = "...";
 
 pub struct Point {
@@ -49,14 +66,53 @@ impl std::fmt::Display for Dimensions {
 	}
 }
 
+/// Returns the number of columns `s` takes in a terminal, where e.g. CJK characters and most
+/// emoji take two columns and combining marks none.
+///
+/// # Examples
+///
+/// ```
+/// # use wlm::display_width;
+/// assert_eq!(5, display_width("Hello"));
+/// assert_eq!(4, display_width("日本"));
+/// assert_eq!(1, display_width("e\u{301}"));
+/// ```
+pub fn display_width(s: &str) -> usize {
+	s.width()
+}
+
+/// Returns the longest run of the grapheme clusters of `s`, from the start or, with `from_end`,
+/// from the end, which takes at most `max_width` columns.
+fn take_width(s: &str, max_width: usize, from_end: bool) -> &str {
+	let mut width = 0;
+	let mut length = 0;
+	let mut take = |grapheme: &str| {
+		width += grapheme.width();
+		if width > max_width {
+			return false;
+		}
+		length += grapheme.len();
+		true
+	};
+	if from_end {
+		s.graphemes(true).rev().take_while(|g| take(g)).count();
+		&s[s.len() - length..]
+	} else {
+		s.graphemes(true).take_while(|g| take(g)).count();
+		&s[..length]
+	}
+}
+
 /// Shrinks a string to a specified maximum length by keeping the left portion
-/// and replacing the right portion with an ellipsis ("...").
+/// and replacing the right portion with an ellipsis ("..."). The string is only cut between
+/// grapheme clusters, so accented letters, emoji and the like are never split.
 ///
 /// # Arguments
 ///
 /// * `s` - The string to shrink.
-/// * `max_length` - The maximum length of the string after shrinking. If `max_length`
-///   is less than or equal to the length of `s`, the original string is returned.
+/// * `max_length` - The maximum length of the string after shrinking, in terminal columns, see
+///   `display_width`. If the length of `s` is less than or equal to `max_length`, the original
+///   string is returned.
 ///   If `max_length` is smaller than the length of the ellipsis, the function
 ///   will return the ellipsis only.
 ///
@@ -83,13 +139,13 @@ impl std::fmt::Display for Dimensions {
 /// ```
 #[allow(dead_code)]
 pub fn shrink_right(s: &str, max_length: usize) -> String {
-	if s.len() <= max_length {
+	if display_width(s) <= max_length {
 		s.to_string()
 	} else if max_length < ELLIPSIS.len() {
-		format!("{}", &ELLIPSIS[..max_length])
+		ELLIPSIS[..max_length].to_string()
 	} else {
 		let effective_length = max_length.saturating_sub(ELLIPSIS.len());
-		format!("{}{}", &s[..effective_length], ELLIPSIS)
+		format!("{}{}", take_width(s, effective_length, false), ELLIPSIS)
 	}
 }
 
@@ -97,6 +153,13 @@ pub fn shrink_right(s: &str, max_length: usize) -> String {
 mod test_shrink_right {
 	use super::*;
 
+	#[test]
+	fn wide_and_combined_characters() {
+		let output = shrink_right("Notes — 日本語 👩‍💻", 12);
+		assert_eq!("Notes — ...", output);
+		assert!(display_width(&output) <= 12);
+	}
+
 	#[test]
 	fn string_less_than_ellipsis() {
 		let input = "He";
@@ -167,8 +230,9 @@ mod test_shrink_right {
 /// # Arguments
 ///
 /// * `s` - The string to shrink.
-/// * `max_length` - The maximum length of the string after shrinking. If `max_length`
-///   is less than or equal to the length of `s`, the original string is returned.
+/// * `max_length` - The maximum length of the string after shrinking, in terminal columns, see
+///   `display_width`. If the length of `s` is less than or equal to `max_length`, the original
+///   string is returned.
 ///   If `max_length` is smaller than the length of the ellipsis, the function
 ///   will return the ellipsis only.
 ///
@@ -195,13 +259,13 @@ mod test_shrink_right {
 /// ```
 #[allow(dead_code)]
 pub fn shrink_left(s: &str, max_length: usize) -> String {
-	if s.len() <= max_length {
+	if display_width(s) <= max_length {
 		s.to_string()
 	} else if max_length < ELLIPSIS.len() {
-		format!("{}", &ELLIPSIS[..max_length])
+		ELLIPSIS[..max_length].to_string()
 	} else {
 		let effective_length = max_length.saturating_sub(ELLIPSIS.len());
-		format!("{}{}", ELLIPSIS, &s[s.len() - effective_length..])
+		format!("{}{}", ELLIPSIS, take_width(s, effective_length, true))
 	}
 }
 
@@ -209,6 +273,13 @@ pub fn shrink_left(s: &str, max_length: usize) -> String {
 mod test_shrink_left {
 	use super::*;
 
+	#[test]
+	fn wide_and_combined_characters() {
+		let output = shrink_left("Notes — 日本語 👩‍💻", 8);
+		assert_eq!("...語 👩‍💻", output);
+		assert!(display_width(&output) <= 8);
+	}
+
 	#[test]
 	fn string_less_than_ellipsis() {
 		let input = "He";
@@ -280,8 +351,9 @@ mod test_shrink_left {
 /// # Arguments
 ///
 /// * `s` - The string to shrink.
-/// * `max_length` - The desired length of the string after shrinking, including the ellipsis.
-///   If `max_length` is less than or equal to the length of `s`, the original string is returned.
+/// * `max_length` - The desired length of the string after shrinking, including the ellipsis, in
+///   terminal columns, see `display_width`. If the length of `s` is less than or equal to
+///   `max_length`, the original string is returned.
 ///   If `max_length` is smaller than the length of the ellipsis, the function will return the
 ///   ellipsis only.
 ///
@@ -309,22 +381,17 @@ mod test_shrink_left {
 /// ```
 #[allow(dead_code)]
 pub fn shrink_center(s: &str, max_length: usize) -> String {
-	let len = s.len();
-	if len <= max_length {
+	if display_width(s) <= max_length {
 		return s.to_string();
 	} else if max_length < ELLIPSIS.len() {
-		return format!("{}", &ELLIPSIS[..max_length]);
+		return ELLIPSIS[..max_length].to_string();
 	}
 
 	// Adjust for the 3 characters in "..."
 	let effective_length = max_length.saturating_sub(ELLIPSIS.len());
-	let half_count = effective_length / 2;
-	let start = &s[..half_count];
-	let end = if effective_length % 2 == 0 {
-		&s[len - half_count..]
-	} else {
-		&s[len - half_count - 1..]
-	};
+	let start = take_width(s, effective_length / 2, false);
+	// The end takes whatever the start leaves, e.g. when a wide character did not fit.
+	let end = take_width(s, effective_length - display_width(start), true);
 
 	format!("{}{}{}", start, ELLIPSIS, end)
 }
@@ -333,6 +400,13 @@ pub fn shrink_center(s: &str, max_length: usize) -> String {
 mod test_shrink_center {
 	use super::*;
 
+	#[test]
+	fn wide_and_combined_characters() {
+		let output = shrink_center("日本語のタイトル", 9);
+		assert_eq!("日...トル", output);
+		assert!(display_width(&output) <= 9);
+	}
+
 	#[test]
 	fn string_less_than_ellipsis() {
 		let input = "He";
@@ -422,9 +496,9 @@ pub trait WindowProvider {
 	/// let screens = provider.screens();
 	/// for screen in screens {
 	///     println!("{}", screen);
-	/// 	for window in screen.windows {
-	///  	   println!("{}", window);
-	///	 	}
+	///     for window in screen.windows {
+	///         println!("{}", window);
+	///     }
 	/// }
 	/// ```
 	fn screens(&self) -> Vec<layout::Screen>;
@@ -448,6 +522,13 @@ pub trait WindowProvider {
 	/// provider.layout(&config);
 	/// ```
 	fn layout(&self, config: &layout::Layout);
+
+	/// Returns the names of the virtual desktops, by index, to resolve a desktop given by name.
+	///
+	/// Providers without virtual desktops return none.
+	fn desktop_names(&self) -> Vec<String> {
+		Vec::new()
+	}
 }
 
 /// Provides a default window provider.
@@ -468,10 +549,11 @@ pub trait WindowProvider {
 /// # Platform-specific Behavior
 ///
 /// - On Windows platforms, this will return a `Win32Provider`.
+/// - On Unix platforms, this will return an `X11Provider`.
 pub fn default_window_provider() -> impl WindowProvider {
 	#[cfg(windows)]
 	let provider = crate::platform::win::Win32Provider::default();
 	#[cfg(unix)]
-	let provider = crate::platform::win::X11Provider::default();
+	let provider = crate::platform::unix::X11Provider;
 	provider
 }
@@ -0,0 +1,567 @@
+use crate::fingerprint::best_match;
+use crate::layout::{
+	Format, Layout, LayoutBuilder, Screen, ScreenBuilder, WindowBuilder, LAYOUT_EXTENSIONS,
+	LAYOUT_VERSION,
+};
+
+use directories::{BaseDirs, ProjectDirs};
+use std::ffi::OsStr;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Expands a leading `~` in `path` into the home directory of the current user.
+///
+/// # Examples
+///
+/// ```
+/// # use std::path::Path;
+/// # use wlm::library::expand_tilde;
+/// assert_eq!(Path::new("/etc/wlm"), expand_tilde("/etc/wlm"));
+/// assert!(!expand_tilde("~/.config/wlm").starts_with("~"));
+/// ```
+pub fn expand_tilde<P: AsRef<Path>>(path: P) -> PathBuf {
+	let path = path.as_ref();
+	match (path.strip_prefix("~"), BaseDirs::new()) {
+		(Ok(rest), Some(dirs)) => dirs.home_dir().join(rest),
+		_ => path.to_path_buf(),
+	}
+}
+
+/// Reads the layout in `layout_file`, determining its format from its extension.
+///
+/// Inherited layouts are not resolved, see `Library::load`.
+pub fn read_layout(layout_file: &Path) -> Result<Layout, String> {
+	let format = layout_format(layout_file)?;
+	let content = std::fs::read_to_string(layout_file)
+		.map_err(|e| format!("Failed to read layout: {}: {}", layout_file.display(), e))?;
+	Layout::parse(&content, format)
+		.map_err(|e| format!("Failed to load layout: {}:{}", layout_file.display(), e))
+}
+
+/// Returns the format of `layout_file` as determined by its extension.
+pub fn layout_format(layout_file: &Path) -> Result<Format, String> {
+	layout_file
+		.extension()
+		.and_then(|ext| ext.to_str())
+		.and_then(Format::from_extension)
+		.ok_or_else(|| format!("Unsupported layout format: {}", layout_file.display()))
+}
+
+/// Parses a layout written in `format`.
+///
+/// Inherited layouts are not resolved, see `Library::resolve`.
+///
+/// # Examples
+///
+/// ```
+/// # use wlm::layout::Format;
+/// # use wlm::library::parse_layout;
+/// let layout = parse_layout("screens:\n- windows:\n  - process: code\n", Format::Yaml).unwrap();
+/// assert_eq!(Some("code".to_string()), layout.screens[0].windows[0].process);
+/// ```
+pub fn parse_layout(content: &str, format: Format) -> Result<Layout, String> {
+	Layout::parse(content, format).map_err(|e| format!("Failed to parse layout: {}", e))
+}
+
+/// Where a layout is loaded from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+	/// The standard input, denoted by `-`.
+	Stdin,
+	/// A layout file.
+	File(PathBuf),
+	/// The name of a layout in the search path.
+	Name(String),
+}
+
+impl Source {
+	/// Determines where to load the layout denoted by `layout` from.
+	///
+	/// `-` denotes the standard input. An existing file, or anything with a path separator or a
+	/// layout file extension, denotes a layout file. Anything else is the name of a layout.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use std::path::PathBuf;
+	/// # use wlm::library::Source;
+	/// assert_eq!(Source::Stdin, Source::parse("-"));
+	/// assert_eq!(Source::File(PathBuf::from("./office.yml")), Source::parse("./office.yml"));
+	/// assert_eq!(Source::File(PathBuf::from("office.toml")), Source::parse("office.toml"));
+	/// assert_eq!(Source::Name("office".to_string()), Source::parse("office"));
+	/// ```
+	pub fn parse<S: AsRef<str>>(layout: S) -> Source {
+		let layout = layout.as_ref();
+		let path = Path::new(layout);
+		if layout == "-" {
+			Source::Stdin
+		} else if path.is_file()
+			|| layout.contains(std::path::is_separator)
+			|| path
+				.extension()
+				.and_then(|ext| ext.to_str())
+				.and_then(Format::from_extension)
+				.is_some()
+		{
+			Source::File(path.to_path_buf())
+		} else {
+			Source::Name(layout.to_string())
+		}
+	}
+}
+
+/// The comment written at the top of a new layout, see `starter_layout`.
+static STARTER_COMMENT: &str = "\
+A wlm layout, applied with `wlm layout NAME`.
+
+Each screen lists rules matching windows by `title` and `process`, which are regular
+expressions, and where to move them. Lengths are in pixels or a percentage of the monitor,
+e.g. w: '50%'. Run `wlm ls` to list the current windows and `wlm schema` for every field.
+";
+
+/// Returns the example layout written by `wlm init`.
+pub fn example_layout() -> Layout {
+	let rule = WindowBuilder::default()
+		.name(Some("editor".to_string()))
+		.process(Some("code".to_string()))
+		.x(Some("0".to_string()))
+		.y(Some("0".to_string()))
+		.w(Some("50%".to_string()))
+		.h(Some("100%".to_string()))
+		.build()
+		.unwrap();
+	LayoutBuilder::default()
+		.version(Some(LAYOUT_VERSION))
+		.screens(vec![ScreenBuilder::default()
+			.id(0)
+			.windows(vec![rule])
+			.build()
+			.unwrap()])
+		.build()
+		.unwrap()
+}
+
+/// Writes `layout` as `format`, preceded by a comment explaining layouts in formats that support
+/// comments.
+///
+/// # Examples
+///
+/// ```
+/// # use wlm::layout::Format;
+/// # use wlm::library::{example_layout, starter_layout};
+/// let yaml = starter_layout(&example_layout(), Format::Yaml).unwrap();
+/// assert!(yaml.starts_with("# A wlm layout"));
+/// assert_eq!(example_layout(), serde_yaml::from_str(&yaml).unwrap());
+/// ```
+pub fn starter_layout(layout: &Layout, format: Format) -> Result<String, String> {
+	let prefix = match format {
+		Format::Yaml | Format::Toml => "#",
+		Format::Ron => "//",
+		_ => return layout.serialize(format),
+	};
+	let mut starter = STARTER_COMMENT
+		.lines()
+		.map(|line| format!("{} {}", prefix, line).trim_end().to_string() + "\n")
+		.collect::<String>();
+	starter.push('\n');
+	starter.push_str(&layout.serialize(format)?);
+	Ok(starter)
+}
+
+/// The directories layouts are searched for in, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Library {
+	dirs: Vec<PathBuf>,
+}
+
+impl Library {
+	pub fn new(dirs: Vec<PathBuf>) -> Self {
+		Library { dirs }
+	}
+
+	/// Creates a `Library` from a search path such as the value of `WLM_LAYOUT_PATH`.
+	///
+	/// Directories are separated the same way as in `PATH` (i.e. with `:` on Unix and `;` on
+	/// Windows), and a leading `~` is expanded into the home directory.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use std::path::PathBuf;
+	/// # use wlm::library::Library;
+	/// # #[cfg(unix)]
+	/// # {
+	/// let library = Library::from_search_path("/srv/team/layouts:/etc/wlm/layouts");
+	/// assert_eq!(
+	///     &[PathBuf::from("/srv/team/layouts"), PathBuf::from("/etc/wlm/layouts")],
+	///     library.dirs()
+	/// );
+	/// # }
+	/// ```
+	pub fn from_search_path<S: AsRef<OsStr>>(search_path: S) -> Self {
+		Library::new(
+			std::env::split_paths(&search_path)
+				.filter(|dir| !dir.as_os_str().is_empty())
+				.map(expand_tilde)
+				.collect(),
+		)
+	}
+
+	pub fn dirs(&self) -> &[PathBuf] {
+		&self.dirs
+	}
+
+	/// Finds the file of the layout named `name`, trying each supported extension in each
+	/// directory in order.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the first directory containing the layout contains it with more than
+	/// one extension.
+	pub fn find<S: AsRef<str>>(&self, name: S) -> Result<Option<PathBuf>, String> {
+		let name = name.as_ref();
+		for dir in &self.dirs {
+			let mut found = LAYOUT_EXTENSIONS
+				.iter()
+				.map(|ext| dir.join(format!("{name}.{ext}")))
+				.filter(|file| file.is_file())
+				.collect::<Vec<PathBuf>>();
+			if found.len() > 1 {
+				return Err(format!(
+					"Ambiguous layout '{}': {}",
+					name,
+					found
+						.iter()
+						.map(|file| file.display().to_string())
+						.collect::<Vec<String>>()
+						.join(", ")
+				));
+			}
+			if let Some(file) = found.pop() {
+				return Ok(Some(file));
+			}
+		}
+		Ok(None)
+	}
+
+	/// Returns the file a new layout named `name` is written to as `format`, which is in the
+	/// first directory.
+	pub fn new_layout_file<S: AsRef<str>>(&self, name: S, format: Format) -> Option<PathBuf> {
+		let extension = format.extension()?;
+		let dir = self.dirs.first()?;
+		Some(dir.join(format!("{}.{}", name.as_ref(), extension)))
+	}
+
+	/// Returns the names of every layout in this library, sorted.
+	pub fn names(&self) -> Vec<String> {
+		let mut names = self
+			.dirs
+			.iter()
+			.filter_map(|dir| std::fs::read_dir(dir).ok())
+			.flatten()
+			.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+			.filter(|file| file.is_file() && layout_format(file).is_ok())
+			.filter_map(|file| Some(file.file_stem()?.to_string_lossy().into_owned()))
+			.collect::<Vec<String>>();
+		names.sort();
+		names.dedup();
+		names
+	}
+
+	/// Returns the name of the layout whose `match_displays` fit the connected `screens` best,
+	/// see `fingerprint::best_match`.
+	///
+	/// Layouts failing to load are skipped.
+	pub fn find_for_displays(&self, screens: &[Screen]) -> Option<String> {
+		let candidates = self.names().into_iter().filter_map(|name| {
+			let layout_file = self.find(&name).ok()??;
+			match read_layout(&layout_file) {
+				Ok(layout) => Some((name, layout.match_displays)),
+				Err(e) => {
+					log::warn!("Skipping layout '{}': {}", name, e);
+					None
+				}
+			}
+		});
+		best_match(candidates, screens)
+	}
+
+	/// Loads the layout named `name`, resolving the layouts it inherits from within this library.
+	///
+	/// Returns `None` if no layout is named `name`.
+	pub fn load<S: AsRef<str>>(&self, name: S) -> Result<Option<Layout>, String> {
+		match self.find(name)? {
+			Some(layout_file) => self.resolve(read_layout(&layout_file)?).map(Some),
+			None => Ok(None),
+		}
+	}
+
+	/// Loads the layout in `layout_file`, resolving the layouts it inherits from within the
+	/// directory of `layout_file` first and then within this library.
+	pub fn load_file(&self, layout_file: &Path) -> Result<Layout, String> {
+		let layout = read_layout(layout_file)?;
+		let mut dirs = layout_file
+			.parent()
+			.map(|dir| vec![dir.to_path_buf()])
+			.unwrap_or_default();
+		dirs.extend(self.dirs.iter().cloned());
+		Library::new(dirs).resolve(layout)
+	}
+
+	/// Loads a layout written in `format` from `reader`, resolving the layouts it inherits from
+	/// within this library.
+	pub fn load_reader(&self, reader: &mut dyn Read, format: Format) -> Result<Layout, String> {
+		let mut content = String::new();
+		reader
+			.read_to_string(&mut content)
+			.map_err(|e| format!("Failed to read layout: {}", e))?;
+		self.resolve(parse_layout(&content, format)?)
+	}
+
+	/// Loads the layout denoted by `source`, reading `stdin` as `format` if need be.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the layout does not exist or fails to load.
+	pub fn load_source(
+		&self,
+		source: &Source,
+		stdin: &mut dyn Read,
+		format: Format,
+	) -> Result<Layout, String> {
+		match source {
+			Source::Stdin => self.load_reader(stdin, format),
+			Source::File(layout_file) if layout_file.is_file() => self.load_file(layout_file),
+			Source::File(layout_file) => {
+				Err(format!("No such layout file: {}", layout_file.display()))
+			}
+			Source::Name(name) => self
+				.load(name)?
+				.ok_or_else(|| format!("No layout named '{}'", name)),
+		}
+	}
+
+	/// Resolves the layouts `layout` inherits from within this library.
+	pub fn resolve(&self, layout: Layout) -> Result<Layout, String> {
+		layout.resolve(|name| match self.find(name)? {
+			Some(layout_file) => read_layout(&layout_file),
+			None => Err(format!("No layout named '{}'", name)),
+		})
+	}
+}
+
+impl Default for Library {
+	/// Returns a `Library` of the `layouts` directory within the platform-specific config
+	/// directory, e.g. `~/.config/wlm/layouts`.
+	fn default() -> Self {
+		let dir = if let Some(config_dir) = ProjectDirs::from("com", "wlm", "wlm") {
+			config_dir.config_dir().join("layouts")
+		} else {
+			expand_tilde("~/.config/wlm/layouts")
+		};
+		Library::new(vec![dir])
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::layout::{Rect, ScreenBuilder};
+	use assert_fs::prelude::*;
+	use assert_fs::TempDir;
+
+	fn library() -> (TempDir, Library) {
+		let temp = TempDir::new().unwrap();
+		temp.child("team/office.yml")
+			.write_str("extends: base\nscreens:\n- id: 0\n  windows:\n  - process: code\n")
+			.unwrap();
+		temp.child("team/base.json")
+			.write_str(r#"{"screens": [{"id": 0, "windows": [{"process": "slack"}]}]}"#)
+			.unwrap();
+		temp.child("personal/office.toml")
+			.write_str("[[screens]]\nid = 0\n[[screens.windows]]\nprocess = \"firefox\"\n")
+			.unwrap();
+		temp.child("personal/ambiguous.yml")
+			.write_str("screens: []\n")
+			.unwrap();
+		temp.child("personal/ambiguous.json")
+			.write_str(r#"{"screens": []}"#)
+			.unwrap();
+		let library = Library::new(vec![
+			temp.child("personal").to_path_buf(),
+			temp.child("team").to_path_buf(),
+		]);
+		(temp, library)
+	}
+
+	#[test]
+	fn find_in_first_directory() {
+		let (temp, library) = library();
+		assert_eq!(
+			Some(temp.child("personal/office.toml").to_path_buf()),
+			library.find("office").unwrap()
+		);
+	}
+
+	#[test]
+	fn find_in_later_directory() {
+		let (temp, library) = library();
+		assert_eq!(
+			Some(temp.child("team/base.json").to_path_buf()),
+			library.find("base").unwrap()
+		);
+	}
+
+	#[test]
+	fn find_missing() {
+		let (_temp, library) = library();
+		assert_eq!(None, library.find("missing").unwrap());
+	}
+
+	#[test]
+	fn find_ambiguous() {
+		let (_temp, library) = library();
+		assert!(library.find("ambiguous").is_err());
+	}
+
+	#[test]
+	fn load_resolves_across_directories() {
+		let (temp, library) = library();
+		// The layout is in the personal directory, the layout it extends in the team one.
+		temp.child("personal/focus.yml")
+			.write_str("extends: base\nscreens:\n- id: 0\n  windows:\n  - process: code\n")
+			.unwrap();
+		let focus = library.load("focus").unwrap().unwrap();
+		let processes = focus.screens[0]
+			.windows
+			.iter()
+			.map(|w| w.process.clone().unwrap())
+			.collect::<Vec<String>>();
+		assert_eq!(vec!["slack", "code"], processes);
+	}
+
+	#[test]
+	fn load_file_resolves_next_to_the_file() {
+		let (temp, _) = library();
+		let office = Library::new(vec![])
+			.load_file(&temp.child("team/office.yml"))
+			.unwrap();
+		assert_eq!(2, office.screens[0].windows.len());
+	}
+
+	#[test]
+	fn load_source_stdin() {
+		let (_temp, library) = library();
+		let mut stdin = "extends: base\n".as_bytes();
+		let layout = library
+			.load_source(&Source::Stdin, &mut stdin, Format::Yaml)
+			.unwrap();
+		assert_eq!(
+			Some("slack".to_string()),
+			layout.screens[0].windows[0].process
+		);
+	}
+
+	#[test]
+	fn load_source_name() {
+		let (_temp, library) = library();
+		let layout = library
+			.load_source(
+				&Source::Name("office".to_string()),
+				&mut std::io::empty(),
+				Format::Yaml,
+			)
+			.unwrap();
+		assert_eq!(
+			Some("firefox".to_string()),
+			layout.screens[0].windows[0].process
+		);
+	}
+
+	#[test]
+	fn load_source_missing() {
+		let (temp, library) = library();
+		for source in [
+			Source::Name("missing".to_string()),
+			Source::File(temp.child("missing.yml").to_path_buf()),
+		] {
+			assert!(library
+				.load_source(&source, &mut std::io::empty(), Format::Yaml)
+				.is_err());
+		}
+	}
+
+	#[test]
+	fn parse_layout_as_table() {
+		assert!(parse_layout("", Format::Table).is_err());
+	}
+
+	#[test]
+	fn from_search_path_skips_empty_entries() {
+		let search_path = std::env::join_paths(["/a", "", "/b"]).unwrap();
+		assert_eq!(
+			&[PathBuf::from("/a"), PathBuf::from("/b")],
+			Library::from_search_path(search_path).dirs()
+		);
+	}
+
+	#[test]
+	fn names() {
+		let (_temp, library) = library();
+		assert_eq!(vec!["ambiguous", "base", "office"], library.names());
+	}
+
+	#[test]
+	fn find_for_displays() {
+		let temp = TempDir::new().unwrap();
+		temp.child("laptop.yml")
+			.write_str("match_displays:\n- name: eDP-*\n")
+			.unwrap();
+		temp.child("docked.toml")
+			.write_str("[[match_displays]]\nname = \"eDP-*\"\n[[match_displays]]\nresolution = \"2560x1440\"\n")
+			.unwrap();
+		temp.child("broken.yml")
+			.write_str("match_displays: 3\n")
+			.unwrap();
+		let library = Library::new(vec![temp.to_path_buf()]);
+		let display = |name: &str, w, h| {
+			ScreenBuilder::default()
+				.name(Some(name.to_string()))
+				.bounds(Rect::new(0, 0, w, h))
+				.build()
+				.unwrap()
+		};
+		let laptop = vec![display("eDP-1", 1920, 1200)];
+		assert_eq!(
+			Some("laptop".to_string()),
+			library.find_for_displays(&laptop)
+		);
+		let docked = vec![display("eDP-1", 1920, 1200), display("DP-2", 2560, 1440)];
+		assert_eq!(
+			Some("docked".to_string()),
+			library.find_for_displays(&docked)
+		);
+		assert_eq!(
+			None,
+			library.find_for_displays(&[display("HDMI-1", 1920, 1080)])
+		);
+	}
+
+	#[test]
+	fn starter_layouts_parse() {
+		for format in [Format::Yaml, Format::Json, Format::Toml, Format::Ron] {
+			let starter = starter_layout(&example_layout(), format).unwrap();
+			assert_eq!(example_layout(), parse_layout(&starter, format).unwrap());
+		}
+	}
+
+	#[test]
+	fn new_layout_file_in_first_directory() {
+		let (temp, library) = library();
+		assert_eq!(
+			Some(temp.child("personal/work.toml").to_path_buf()),
+			library.new_layout_file("work", Format::Toml)
+		);
+		assert_eq!(None, library.new_layout_file("work", Format::Table));
+	}
+}
@@ -0,0 +1,673 @@
+use crate::fingerprint::DisplayMatcher;
+use crate::layout::{Desktop, Format, Screen, Window};
+
+use regex::Regex;
+use serde_json::Value;
+
+/// The fields of a listed window, in the order of the columns of `csv` and `tsv` listings.
+pub static LISTING_FIELDS: [&str; 13] = [
+	"screen", "title", "process", "x", "y", "w", "h", "geometry", "desktop", "sticky", "state",
+	"id", "pid",
+];
+
+/// The columns of the `wlm ls` table unless others are given.
+pub static DEFAULT_COLUMNS: &str = "title,process,geometry,desktop";
+
+/// Returns the field `name` of `window` on `screen`, as listed by `WindowProvider::screens`, see
+/// `LISTING_FIELDS`. Missing and unknown fields are empty.
+pub fn field(screen: &Screen, window: &Window, name: &str) -> String {
+	let text = |value: &Option<String>| value.clone().unwrap_or_default();
+	match name {
+		"screen" => screen.id.map(|id| id.to_string()).unwrap_or_default(),
+		"id" => window.id.map(|id| format!("{:#x}", id)).unwrap_or_default(),
+		"pid" => window.pid.map(|pid| pid.to_string()).unwrap_or_default(),
+		"title" => text(&window.title),
+		"process" => text(&window.process),
+		"x" => text(&window.x),
+		"y" => text(&window.y),
+		"w" => text(&window.w),
+		"h" => text(&window.h),
+		"desktop" => window
+			.desktop
+			.as_ref()
+			.map(|d| d.to_string())
+			.unwrap_or_default(),
+		"geometry" => match (&window.x, &window.y, &window.w, &window.h) {
+			(Some(x), Some(y), Some(w), Some(h)) => {
+				let offset = |o: &str| {
+					if o.starts_with('-') {
+						o.to_string()
+					} else {
+						format!("+{}", o)
+					}
+				};
+				format!("{}x{}{}{}", w, h, offset(x), offset(y))
+			}
+			_ => String::new(),
+		},
+		"sticky" => window.sticky.map(|s| s.to_string()).unwrap_or_default(),
+		"state" => state(window),
+		_ => String::new(),
+	}
+}
+
+/// Returns whether `window` is minimized or maximized, and along which axis, or nothing if it is
+/// neither.
+fn state(window: &Window) -> String {
+	let is = |flag: Option<bool>| flag == Some(true);
+	if is(window.minimized) {
+		"minimized"
+	} else if is(window.maximized)
+		|| (is(window.maximized_vertical) && is(window.maximized_horizontal))
+	{
+		"maximized"
+	} else if is(window.maximized_vertical) {
+		"maximized vertically"
+	} else if is(window.maximized_horizontal) {
+		"maximized horizontally"
+	} else {
+		""
+	}
+	.to_string()
+}
+
+/// Returns `window` on `screen` as a JSON object, with the id of the screen as `screen`.
+fn record(screen: &Screen, window: &Window) -> Value {
+	let mut record = serde_json::to_value(window).unwrap();
+	if let (Value::Object(map), Some(id)) = (&mut record, screen.id) {
+		map.insert("screen".to_string(), Value::from(id));
+	}
+	record
+}
+
+/// Quotes `value` for a CSV file if it contains a comma, a quote or a line break.
+fn csv_escape(value: &str) -> String {
+	if value.contains([',', '"', '\n', '\r']) {
+		format!("\"{}\"", value.replace('"', "\"\""))
+	} else {
+		value.to_string()
+	}
+}
+
+/// Escapes tabs, line breaks and backslashes in `value` for a TSV file.
+fn tsv_escape(value: &str) -> String {
+	value
+		.replace('\\', r"\\")
+		.replace('\t', r"\t")
+		.replace('\n', r"\n")
+		.replace('\r', r"\r")
+}
+
+/// Lists the windows on `screens`, as listed by `WindowProvider::screens`, as `format`, one line
+/// per window: `ndjson` writes each window as a JSON object, while `csv` and `tsv` write a header
+/// and the `LISTING_FIELDS` of each window.
+///
+/// # Errors
+///
+/// Returns an error if `format` is not one of `ndjson`, `csv` or `tsv`.
+///
+/// # Examples
+///
+/// ```
+/// # use wlm::layout::{Format, ScreenBuilder, WindowBuilder};
+/// # use wlm::listing::list;
+/// let window = WindowBuilder::default()
+///     .title(Some("Inbox, 3 unread".to_string()))
+///     .process(Some("thunderbird".to_string()))
+///     .build()
+///     .unwrap();
+/// let screen = ScreenBuilder::default().id(0).windows(vec![window]).build().unwrap();
+/// let csv = list(&[screen], Format::Csv).unwrap();
+/// assert_eq!(
+///     "screen,title,process,x,y,w,h,geometry,desktop,sticky,state,id,pid\n\
+///      0,\"Inbox, 3 unread\",thunderbird,,,,,,,,,,\n",
+///     csv
+/// );
+/// ```
+pub fn list(screens: &[Screen], format: Format) -> Result<String, String> {
+	let windows = screens
+		.iter()
+		.flat_map(|screen| screen.windows.iter().map(move |window| (screen, window)));
+	let delimited = |delimiter: &str, escape: fn(&str) -> String| {
+		let rows = windows.clone().map(|(screen, window)| {
+			LISTING_FIELDS
+				.iter()
+				.map(|name| escape(&field(screen, window, name)))
+				.collect::<Vec<String>>()
+				.join(delimiter)
+		});
+		std::iter::once(LISTING_FIELDS.join(delimiter))
+			.chain(rows)
+			.map(|line| line + "\n")
+			.collect::<String>()
+	};
+	match format {
+		Format::Ndjson => Ok(windows
+			.clone()
+			.map(|(screen, window)| record(screen, window).to_string() + "\n")
+			.collect()),
+		Format::Csv => Ok(delimited(",", csv_escape)),
+		Format::Tsv => Ok(delimited("\t", tsv_escape)),
+		format => Err(format!(
+			"Windows cannot be listed line by line as {}",
+			format
+		)),
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+	Text(String),
+	Field(String),
+}
+
+/// A line written for each listed window, where `{field}` is replaced with a field of the window,
+/// see `LISTING_FIELDS`.
+///
+/// `{{` and `}}` write a brace, and `\t`, `\n` and `\\` a tab, a line break and a backslash, so
+/// templates can be written in a shell without quoting them twice.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+	parts: Vec<Part>,
+}
+
+impl Template {
+	/// Parses a template such as `{title}\t{process}\t{x},{y}`.
+	///
+	/// # Errors
+	///
+	/// Returns an error if a field is unknown or its braces are not closed.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use wlm::layout::{Screen, WindowBuilder};
+	/// # use wlm::listing::Template;
+	/// let template = Template::parse(r"{process}\t{x},{y}").unwrap();
+	/// let window = WindowBuilder::default()
+	///     .process(Some("code".to_string()))
+	///     .x(Some("0".to_string()))
+	///     .y(Some("40".to_string()))
+	///     .build()
+	///     .unwrap();
+	/// assert_eq!("code\t0,40", template.render(&Screen::new(), &window));
+	/// assert!(Template::parse("{colour}").is_err());
+	/// ```
+	pub fn parse(template: &str) -> Result<Template, String> {
+		let mut parts = Vec::new();
+		let mut text = String::new();
+		let mut chars = template.chars().peekable();
+		while let Some(c) = chars.next() {
+			match (c, chars.peek()) {
+				('{', Some('{')) | ('}', Some('}')) => {
+					text.push(c);
+					chars.next();
+				}
+				('\\', Some(&escaped @ ('t' | 'n' | '\\'))) => {
+					text.push(match escaped {
+						't' => '\t',
+						'n' => '\n',
+						_ => '\\',
+					});
+					chars.next();
+				}
+				('{', _) => {
+					let mut name = String::new();
+					loop {
+						match chars.next() {
+							Some('}') => break,
+							Some(c) => name.push(c),
+							None => return Err(format!("Unclosed '{{{}' in template", name)),
+						}
+					}
+					if !LISTING_FIELDS.contains(&name.as_str()) {
+						return Err(format!(
+							"Unknown field '{{{}}}' in template, expected one of {:?}",
+							name, LISTING_FIELDS
+						));
+					}
+					if !text.is_empty() {
+						parts.push(Part::Text(std::mem::take(&mut text)));
+					}
+					parts.push(Part::Field(name));
+				}
+				('}', _) => return Err("Unmatched '}' in template, write '}}' instead".to_string()),
+				(c, _) => text.push(c),
+			}
+		}
+		if !text.is_empty() {
+			parts.push(Part::Text(text));
+		}
+		Ok(Template { parts })
+	}
+
+	/// Writes the template for `window` on `screen`, as listed by `WindowProvider::screens`.
+	pub fn render(&self, screen: &Screen, window: &Window) -> String {
+		self.parts
+			.iter()
+			.map(|part| match part {
+				Part::Text(text) => text.clone(),
+				Part::Field(name) => field(screen, window, name),
+			})
+			.collect()
+	}
+}
+
+/// A column of the `wlm ls` table: a field of the listed windows, see `LISTING_FIELDS`, and the
+/// most columns of the terminal it may take, if limited.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+	pub field: String,
+	pub width: Option<usize>,
+}
+
+impl Column {
+	/// Parses a comma-separated list of columns, where each field may be followed by the most
+	/// columns of the terminal it may take, e.g. `title:40,process,geometry`.
+	///
+	/// # Errors
+	///
+	/// Returns an error if a field is unknown or a width is not a number.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use wlm::listing::Column;
+	/// let columns = Column::parse_list("title:40,pid").unwrap();
+	/// assert_eq!("title", columns[0].field);
+	/// assert_eq!(Some(40), columns[0].width);
+	/// assert_eq!(None, columns[1].width);
+	/// assert!(Column::parse_list("title,colour").is_err());
+	/// ```
+	pub fn parse_list(columns: &str) -> Result<Vec<Column>, String> {
+		columns
+			.split(',')
+			.map(|column| {
+				let (field, width) = match column.trim().split_once(':') {
+					Some((field, width)) => {
+						let width = width
+							.parse::<usize>()
+							.map_err(|_| format!("Invalid width of column '{}'", column))?;
+						(field, Some(width))
+					}
+					None => (column.trim(), None),
+				};
+				if !LISTING_FIELDS.contains(&field) {
+					return Err(format!(
+						"Unknown column '{}', expected one of {:?}",
+						field, LISTING_FIELDS
+					));
+				}
+				Ok(Column {
+					field: field.to_string(),
+					width,
+				})
+			})
+			.collect()
+	}
+}
+
+/// The narrowest a column is shrunk to so that a table fits the terminal.
+pub const MIN_COLUMN_WIDTH: usize = 8;
+
+/// Returns the width of each column of a table whose contents take `natural` widths, so that the
+/// table, with `gap` columns between its columns, fits `available` columns of the terminal.
+///
+/// The widest columns are shrunk first, though none narrower than `MIN_COLUMN_WIDTH` unless they
+/// already are, so a table may still not fit a very narrow terminal.
+///
+/// # Examples
+///
+/// ```
+/// # use wlm::listing::fit_widths;
+/// assert_eq!(vec![30, 20, 9], fit_widths(&[30, 20, 9], 80, 2));
+/// assert_eq!(vec![22, 20, 9], fit_widths(&[60, 20, 9], 55, 2));
+/// ```
+pub fn fit_widths(natural: &[usize], available: usize, gap: usize) -> Vec<usize> {
+	let mut widths = natural.to_vec();
+	let available = available.saturating_sub(gap * natural.len().saturating_sub(1));
+	while widths.iter().sum::<usize>() > available {
+		let widest = widths
+			.iter()
+			.enumerate()
+			.filter(|(_, width)| **width > MIN_COLUMN_WIDTH)
+			.max_by_key(|(i, width)| (**width, std::cmp::Reverse(*i)))
+			.map(|(i, _)| i);
+		match widest {
+			Some(i) => widths[i] -= 1,
+			None => break,
+		}
+	}
+	widths
+}
+
+/// Which of the listed windows to keep, e.g. to try out the `title` and `process` of a rule.
+///
+/// Every specified attribute must match the window.
+#[derive(Debug, Default, Clone, PartialEq, Builder)]
+#[builder(setter(into))]
+pub struct Filter {
+	/// A regular expression the title must match, as in a rule.
+	#[builder(default)]
+	pub title: Option<String>,
+
+	/// A regular expression the process must match, as in a rule.
+	#[builder(default)]
+	pub process: Option<String>,
+
+	/// The screen the window must be on: its id, a glob its connector name must match, e.g.
+	/// `'DP-*'`, or its EDID fingerprint.
+	#[builder(default)]
+	pub screen: Option<String>,
+
+	/// The virtual desktop the window must be on, which windows on all desktops always are.
+	#[builder(default)]
+	pub desktop: Option<Desktop>,
+
+	/// The names of the virtual desktops, as returned by `WindowProvider::desktop_names`, to
+	/// match windows on a desktop given by name.
+	#[builder(default)]
+	pub desktop_names: Vec<String>,
+}
+
+impl Filter {
+	/// Returns an error if the title or process is not a valid regular expression.
+	pub fn check(&self) -> Result<(), String> {
+		for pattern in self.title.iter().chain(&self.process) {
+			Regex::new(pattern)
+				.map_err(|e| format!("Invalid regular expression '{}': {}", pattern, e))?;
+		}
+		Ok(())
+	}
+
+	/// Returns whether `window` on `screen`, as listed by `WindowProvider::screens`, is kept.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use wlm::layout::{ScreenBuilder, WindowBuilder};
+	/// # use wlm::listing::FilterBuilder;
+	/// let screen = ScreenBuilder::default().id(1).name(Some("DP-2".to_string())).build().unwrap();
+	/// let window = WindowBuilder::default().process(Some("/usr/bin/code".to_string())).build().unwrap();
+	/// let filter = FilterBuilder::default()
+	///     .process(Some("code".to_string()))
+	///     .screen(Some("DP-*".to_string()))
+	///     .build()
+	///     .unwrap();
+	/// assert!(filter.keeps(&screen, &window));
+	/// let filter = FilterBuilder::default().screen(Some("0".to_string())).build().unwrap();
+	/// assert!(!filter.keeps(&screen, &window));
+	/// ```
+	pub fn keeps(&self, screen: &Screen, window: &Window) -> bool {
+		if self.title.is_some() || self.process.is_some() {
+			let selector = Window {
+				title: self.title.clone(),
+				process: self.process.clone(),
+				..Window::new()
+			};
+			if !selector.matches(window) {
+				return false;
+			}
+		}
+		if let Some(selector) = &self.screen {
+			let matches = match selector.parse::<u8>() {
+				Ok(id) => screen.id == Some(id),
+				Err(_) => [
+					DisplayMatcher {
+						name: Some(selector.clone()),
+						..DisplayMatcher::default()
+					},
+					DisplayMatcher {
+						edid: Some(selector.clone()),
+						..DisplayMatcher::default()
+					},
+				]
+				.iter()
+				.any(|matcher| matcher.score(screen).is_some()),
+			};
+			if !matches {
+				return false;
+			}
+		}
+		if let Some(desktop) = &self.desktop {
+			let on_desktop = match (
+				desktop.resolve(&self.desktop_names),
+				window
+					.desktop
+					.as_ref()
+					.and_then(|d| d.resolve(&self.desktop_names)),
+			) {
+				(Some(wanted), Some(index)) => wanted == index,
+				_ => window.desktop.as_ref() == Some(desktop),
+			};
+			if window.sticky != Some(true) && !on_desktop {
+				return false;
+			}
+		}
+		true
+	}
+}
+
+/// The names of the `SortKey`s, as given to `wlm ls --sort`.
+pub static SORT_KEYS: [&str; 4] = ["x", "title", "process", "area"];
+
+/// What to sort the listed windows of each screen by.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+	/// The left edge, from left to right.
+	X,
+	/// The title, alphabetically and ignoring case.
+	Title,
+	/// The process, alphabetically and ignoring case.
+	Process,
+	/// The width times the height, from the smallest to the largest.
+	Area,
+}
+
+impl std::str::FromStr for SortKey {
+	type Err = String;
+
+	fn from_str(key: &str) -> Result<SortKey, Self::Err> {
+		match key {
+			"x" => Ok(SortKey::X),
+			"title" => Ok(SortKey::Title),
+			"process" => Ok(SortKey::Process),
+			"area" => Ok(SortKey::Area),
+			_ => Err(format!(
+				"Unknown sort key '{}', expected one of {:?}",
+				key, SORT_KEYS
+			)),
+		}
+	}
+}
+
+/// Keeps the windows on `screens`, as listed by `WindowProvider::screens`, that `filter` keeps,
+/// sorting those of each screen by `sort`. Screens stay in order, even when none of their windows
+/// are kept, so listings still describe every monitor.
+pub fn select(screens: Vec<Screen>, filter: &Filter, sort: Option<SortKey>) -> Vec<Screen> {
+	let pixels = |length: &Option<String>| length.as_deref().and_then(|l| l.parse::<i64>().ok());
+	let lowercase = |text: &Option<String>| text.as_deref().map(str::to_lowercase);
+	screens
+		.into_iter()
+		.map(|screen| {
+			let mut windows = screen
+				.windows
+				.iter()
+				.filter(|window| filter.keeps(&screen, window))
+				.cloned()
+				.collect::<Vec<Window>>();
+			match sort {
+				Some(SortKey::X) => windows.sort_by_key(|w| pixels(&w.x)),
+				Some(SortKey::Title) => windows.sort_by_key(|w| lowercase(&w.title)),
+				Some(SortKey::Process) => windows.sort_by_key(|w| lowercase(&w.process)),
+				Some(SortKey::Area) => {
+					windows.sort_by_key(|w| pixels(&w.w).zip(pixels(&w.h)).map(|(w, h)| w * h))
+				}
+				None => {}
+			}
+			Screen { windows, ..screen }
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::layout::{Desktop, ScreenBuilder, WindowBuilder};
+
+	fn screens() -> Vec<Screen> {
+		let window = |title: &str, process: &str| {
+			WindowBuilder::default()
+				.title(Some(title.to_string()))
+				.process(Some(process.to_string()))
+				.x(Some("1920".to_string()))
+				.y(Some("0".to_string()))
+				.w(Some("960".to_string()))
+				.h(Some("1080".to_string()))
+				.desktop(Some(Desktop::from(2)))
+				.build()
+				.unwrap()
+		};
+		vec![ScreenBuilder::default()
+			.id(1)
+			.windows(vec![
+				window("\"Notes\"\tdraft", "/usr/bin/gedit"),
+				window(r"C:\Users", "explorer.exe"),
+			])
+			.build()
+			.unwrap()]
+	}
+
+	#[test]
+	fn list_ndjson() {
+		let ndjson = list(&screens(), Format::Ndjson).unwrap();
+		let records = ndjson
+			.lines()
+			.map(|line| serde_json::from_str::<Value>(line).unwrap())
+			.collect::<Vec<Value>>();
+		assert_eq!(2, records.len());
+		assert_eq!(1, records[0]["screen"]);
+		assert_eq!("/usr/bin/gedit", records[0]["process"]);
+		assert_eq!("960", records[1]["w"]);
+	}
+
+	#[test]
+	fn list_csv_quotes() {
+		let csv = list(&screens(), Format::Csv).unwrap();
+		let lines = csv.lines().collect::<Vec<&str>>();
+		assert_eq!(
+			"screen,title,process,x,y,w,h,geometry,desktop,sticky,state,id,pid",
+			lines[0]
+		);
+		assert_eq!(
+			"1,\"\"\"Notes\"\"\tdraft\",/usr/bin/gedit,1920,0,960,1080,960x1080+1920+0,2,,,,",
+			lines[1]
+		);
+	}
+
+	#[test]
+	fn list_tsv_escapes() {
+		let tsv = list(&screens(), Format::Tsv).unwrap();
+		let lines = tsv.lines().collect::<Vec<&str>>();
+		assert_eq!(3, lines.len());
+		assert!(lines[1].starts_with("1\t\"Notes\"\\tdraft\t/usr/bin/gedit\t"));
+		assert!(lines[2].starts_with(r"1	C:\\Users	explorer.exe	"));
+		assert!(list(&screens(), Format::Yaml).is_err());
+	}
+
+	#[test]
+	fn select_by_desktop_and_sort() {
+		let mut screens = screens();
+		screens[0].windows[1].desktop = Some(Desktop::from(0));
+		screens[0].windows[1].w = Some("100".to_string());
+		let filter = FilterBuilder::default()
+			.desktop(Some(Desktop::from(2)))
+			.build()
+			.unwrap();
+		let selected = select(screens.clone(), &filter, None);
+		assert_eq!(1, selected[0].windows.len());
+		assert_eq!(
+			Some("/usr/bin/gedit".to_string()),
+			selected[0].windows[0].process
+		);
+		screens[0].windows[1].sticky = Some(true);
+		assert_eq!(2, select(screens.clone(), &filter, None)[0].windows.len());
+		let sorted = select(screens, &Filter::default(), Some(SortKey::Area));
+		assert_eq!(Some("100".to_string()), sorted[0].windows[0].w);
+	}
+
+	#[test]
+	fn select_by_desktop_name() {
+		let filter = FilterBuilder::default()
+			.desktop(Some(Desktop::from("work")))
+			.desktop_names(vec![
+				"main".to_string(),
+				"web".to_string(),
+				"work".to_string(),
+			])
+			.build()
+			.unwrap();
+		assert_eq!(2, select(screens(), &filter, None)[0].windows.len());
+		let filter = FilterBuilder::default()
+			.desktop(Some(Desktop::from("web")))
+			.desktop_names(vec![
+				"main".to_string(),
+				"web".to_string(),
+				"work".to_string(),
+			])
+			.build()
+			.unwrap();
+		assert!(select(screens(), &filter, None)[0].windows.is_empty());
+	}
+
+	#[test]
+	fn select_keeps_screens() {
+		let filter = FilterBuilder::default()
+			.title(Some("^Slack$".to_string()))
+			.build()
+			.unwrap();
+		let selected = select(screens(), &filter, Some(SortKey::Title));
+		assert_eq!(1, selected.len());
+		assert!(selected[0].windows.is_empty());
+		assert!(FilterBuilder::default()
+			.process(Some("(".to_string()))
+			.build()
+			.unwrap()
+			.check()
+			.is_err());
+	}
+
+	#[test]
+	fn fields() {
+		let mut window = screens()[0].windows[0].clone();
+		window.id = Some(0x3a00007);
+		window.x = Some("-8".to_string());
+		window.maximized_vertical = Some(true);
+		assert_eq!("0x3a00007", field(&screens()[0], &window, "id"));
+		assert_eq!("960x1080-8+0", field(&screens()[0], &window, "geometry"));
+		assert_eq!(
+			"maximized vertically",
+			field(&screens()[0], &window, "state")
+		);
+		window.minimized = Some(true);
+		assert_eq!("minimized", field(&screens()[0], &window, "state"));
+	}
+
+	#[test]
+	fn fit_widths_to_narrow_terminals() {
+		assert_eq!(vec![10, 10], fit_widths(&[40, 10], 21, 1));
+		assert_eq!(vec![8, 8, 4], fit_widths(&[40, 30, 4], 10, 2));
+	}
+
+	#[test]
+	fn template_escapes() {
+		let template = Template::parse(r"{{{screen}}} {title}\n").unwrap();
+		let screens = screens();
+		assert_eq!(
+			"{1} C:\\Users\n",
+			template.render(&screens[0], &screens[0].windows[1])
+		);
+		assert!(Template::parse("{title").is_err());
+		assert!(Template::parse("title}").is_err());
+	}
+}
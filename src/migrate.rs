@@ -0,0 +1,133 @@
+use crate::layout::{Format, Layout, ParseError, Screen, Window, LAYOUT_VERSION};
+
+/// A config of wlm 0.3, a flat list of windows with pixel coordinates.
+///
+/// ```json
+/// {"windows": [{"title": "Inbox - Thunderbird", "x": 0, "y": 0, "w": 960, "h": 1080}]}
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LegacyLayout {
+	windows: Vec<LegacyWindow>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LegacyWindow {
+	title: Option<String>,
+	process: Option<String>,
+	x: Option<i32>,
+	y: Option<i32>,
+	w: Option<i32>,
+	h: Option<i32>,
+}
+
+impl From<LegacyWindow> for Window {
+	/// Converts a window of wlm 0.3, whose title and process were plain text, into a rule
+	/// matching the same windows.
+	fn from(legacy: LegacyWindow) -> Self {
+		let escape = |value: Option<String>| value.map(|v| regex::escape(&v));
+		let length = |value: Option<i32>| value.map(|v| v.to_string());
+		Window {
+			title: escape(legacy.title),
+			process: escape(legacy.process),
+			x: length(legacy.x),
+			y: length(legacy.y),
+			w: length(legacy.w),
+			h: length(legacy.h),
+			..Window::default()
+		}
+	}
+}
+
+/// Parses `content` as a config of wlm 0.3, converting it into a layout with a single screen.
+///
+/// Returns `None` if `content` is not such a config.
+pub fn parse_legacy(content: &str, format: Format) -> Option<Layout> {
+	let legacy: LegacyLayout = match format {
+		Format::Yaml => serde_yaml::from_str(content).ok()?,
+		Format::Json => serde_json::from_str(content).ok()?,
+		Format::Toml => toml::from_str(content).ok()?,
+		Format::Ron => ron::from_str(content).ok()?,
+		Format::Table | Format::Ndjson | Format::Csv | Format::Tsv => return None,
+	};
+	let screen = Screen {
+		windows: legacy.windows.into_iter().map(Window::from).collect(),
+		..Screen::new()
+	};
+	Some(Layout {
+		version: Some(LAYOUT_VERSION),
+		screens: vec![screen],
+		..Layout::new()
+	})
+}
+
+/// Migrates the layout in `content`, written in `format`, to the current layout version.
+///
+/// # Errors
+///
+/// Returns an error if `content` is neither a layout nor a config of wlm 0.3.
+///
+/// # Examples
+///
+/// ```
+/// # use wlm::layout::{Format, LAYOUT_VERSION};
+/// # use wlm::migrate::migrate;
+/// let legacy = r#"{"windows": [{"title": "Inbox (1)", "x": 0, "y": 0, "w": 960, "h": 1080}]}"#;
+/// let layout = migrate(legacy, Format::Json).unwrap();
+/// assert_eq!(Some(LAYOUT_VERSION), layout.version);
+/// let rule = &layout.screens[0].windows[0];
+/// assert_eq!(Some(r"Inbox \(1\)".to_string()), rule.title);
+/// assert_eq!(Some("960".to_string()), rule.w);
+/// ```
+pub fn migrate(content: &str, format: Format) -> Result<Layout, ParseError> {
+	let mut layout = match parse_legacy(content, format) {
+		Some(layout) => layout,
+		None => Layout::parse(content, format)?,
+	};
+	layout.version = Some(LAYOUT_VERSION);
+	Ok(layout)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parse_legacy_config() {
+		let legacy = r#"{
+			"windows": [
+				{"title": "Inbox - Thunderbird", "process": "thunderbird.exe", "x": 0, "y": 0, "w": 960, "h": 1080},
+				{"process": "slack.exe", "x": -8, "y": 0}
+			]
+		}"#;
+		let layout = parse_legacy(legacy, Format::Json).unwrap();
+		assert_eq!(1, layout.screens.len());
+		let windows = &layout.screens[0].windows;
+		assert_eq!(2, windows.len());
+		assert_eq!(Some(r"thunderbird\.exe".to_string()), windows[0].process);
+		assert_eq!(Some("1080".to_string()), windows[0].h);
+		assert_eq!(Some("-8".to_string()), windows[1].x);
+		assert_eq!(None, windows[1].w);
+	}
+
+	#[test]
+	fn parse_legacy_ignores_layouts() {
+		assert!(parse_legacy(r#"{"screens": []}"#, Format::Json).is_none());
+		assert!(parse_legacy(r#"{"windows": [], "screens": []}"#, Format::Json).is_none());
+	}
+
+	#[test]
+	fn migrate_current_layout() {
+		let layout = migrate("screens:\n- windows:\n  - process: code\n", Format::Yaml).unwrap();
+		assert_eq!(Some(LAYOUT_VERSION), layout.version);
+		assert_eq!(
+			Some("code".to_string()),
+			layout.screens[0].windows[0].process
+		);
+	}
+
+	#[test]
+	fn migrate_invalid() {
+		assert!(migrate(r#"{"windows": [{"x": "left"}]}"#, Format::Json).is_err());
+	}
+}
@@ -0,0 +1,353 @@
+use crate::layout::{Desktop, Layout, Length, Rect, Screen, Window};
+use crate::WindowProvider;
+
+use std::cell::RefCell;
+
+/// A `WindowProvider` backed by in-memory screens rather than a real windowing system.
+///
+/// Laying out windows updates the in-memory windows the same way a platform provider would
+/// move the real ones, which makes it possible to exercise layouts without a display.
+///
+/// # Examples
+///
+/// ```
+/// # use wlm::layout::{LayoutBuilder, ScreenBuilder, WindowBuilder};
+/// # use wlm::platform::fake::FakeProvider;
+/// # use wlm::WindowProvider;
+/// let live = WindowBuilder::default().title(Some("Inbox".to_string())).build().unwrap();
+/// let provider = FakeProvider::new(vec![ScreenBuilder::default().windows(vec![live]).build().unwrap()]);
+/// let rule = WindowBuilder::default()
+///     .title(Some("Inbox".to_string()))
+///     .w(Some("50%".to_string()))
+///     .build()
+///     .unwrap();
+/// let screen = ScreenBuilder::default().windows(vec![rule]).build().unwrap();
+/// provider.layout(&LayoutBuilder::default().screens(vec![screen]).build().unwrap());
+/// assert_eq!(Some("960".to_string()), provider.screens()[0].windows[0].w);
+/// ```
+#[derive(Debug)]
+pub struct FakeProvider {
+	screens: RefCell<Vec<Screen>>,
+	focused: RefCell<Option<(usize, usize)>>,
+	desktops: Vec<String>,
+	width: i32,
+	height: i32,
+}
+
+impl FakeProvider {
+	pub fn new(screens: Vec<Screen>) -> Self {
+		FakeProvider {
+			screens: RefCell::new(screens),
+			focused: RefCell::new(None),
+			desktops: vec!["0".to_string()],
+			width: 1920,
+			height: 1080,
+		}
+	}
+
+	/// Sets the names of the available virtual desktops.
+	pub fn desktops<S: Into<String>>(mut self, names: Vec<S>) -> Self {
+		self.desktops = names.into_iter().map(Into::into).collect();
+		self
+	}
+
+	/// Sets the size of every monitor, which percentages are resolved against. Monitors without
+	/// explicit bounds are placed side by side.
+	pub fn monitor(mut self, width: i32, height: i32) -> Self {
+		self.width = width;
+		self.height = height;
+		self
+	}
+
+	/// Returns the window that was focused by the last layout, if any.
+	pub fn focused(&self) -> Option<Window> {
+		let (screen, window) = (*self.focused.borrow())?;
+		Some(self.screens.borrow()[screen].windows[window].clone())
+	}
+
+	/// Returns the bounds of the monitor of the `i`th screen.
+	fn bounds(&self, i: usize, screen: &Screen) -> Rect {
+		screen
+			.bounds
+			.unwrap_or_else(|| Rect::new(i as i32 * self.width, 0, self.width, self.height))
+	}
+
+	fn apply(&self, live: &mut Window, rule: &Window, bounds: Rect) {
+		let resolve = |value: &Option<String>, origin: i32, extent: i32| {
+			value
+				.as_ref()
+				.and_then(|v| v.parse::<Length>().ok())
+				.map(|length| length.to_position(origin, extent).to_string())
+		};
+		if let Some(x) = resolve(&rule.x, bounds.x, bounds.w) {
+			live.x = Some(x);
+		}
+		if let Some(y) = resolve(&rule.y, bounds.y, bounds.h) {
+			live.y = Some(y);
+		}
+		if let Some(w) = resolve(&rule.w, 0, bounds.w) {
+			live.w = Some(w);
+		}
+		if let Some(h) = resolve(&rule.h, 0, bounds.h) {
+			live.h = Some(h);
+		}
+		for (state, value) in [
+			(&mut live.maximized, rule.maximized),
+			(&mut live.maximized_vertical, rule.maximized_vertical),
+			(&mut live.maximized_horizontal, rule.maximized_horizontal),
+			(&mut live.minimized, rule.minimized),
+		] {
+			if value.is_some() {
+				*state = value.filter(|state| *state);
+			}
+		}
+		if rule.sticky == Some(true) {
+			live.sticky = Some(true);
+			live.desktop = None;
+		} else if let Some(desktop) = &rule.desktop {
+			match desktop.resolve(&self.desktops) {
+				Some(index) => {
+					live.sticky = None;
+					live.desktop = Some(Desktop::Index(index));
+				}
+				None => log::warn!("No such desktop: {}", desktop),
+			}
+		}
+	}
+}
+
+impl WindowProvider for FakeProvider {
+	fn screens(&self) -> Vec<Screen> {
+		let mut screens = self.screens.borrow().clone();
+		for (i, screen) in screens.iter_mut().enumerate() {
+			screen.bounds = Some(self.bounds(i, screen));
+		}
+		screens
+	}
+
+	fn layout(&self, layout: &Layout) {
+		let mut screens = self.screens.borrow_mut();
+		for rule in layout.screens.iter().flat_map(|s| &s.windows) {
+			let position = screens.iter().enumerate().find_map(|(i, s)| {
+				s.windows
+					.iter()
+					.position(|w| rule.matches(w))
+					.map(|j| (i, j))
+			});
+			if let Some((i, j)) = position {
+				let bounds = self.bounds(i, &screens[i]);
+				self.apply(&mut screens[i].windows[j], rule, bounds);
+				if rule.focus == Some(true) {
+					*self.focused.borrow_mut() = Some((i, j));
+				}
+			}
+		}
+	}
+
+	fn desktop_names(&self) -> Vec<String> {
+		self.desktops.clone()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::journal::Journal;
+	use crate::layout::{LayoutBuilder, ScreenBuilder, WindowBuilder};
+
+	fn provider() -> FakeProvider {
+		let windows = vec![
+			WindowBuilder::default()
+				.title(Some("Inbox - Thunderbird".to_string()))
+				.process(Some("thunderbird".to_string()))
+				.desktop(Some(Desktop::Index(0)))
+				.build()
+				.unwrap(),
+			WindowBuilder::default()
+				.title(Some("general | Slack".to_string()))
+				.process(Some("slack".to_string()))
+				.desktop(Some(Desktop::Index(0)))
+				.build()
+				.unwrap(),
+		];
+		FakeProvider::new(vec![ScreenBuilder::default()
+			.id(0)
+			.windows(windows)
+			.build()
+			.unwrap()])
+		.desktops(vec!["main", "web", "code", "comms"])
+	}
+
+	fn layout(rule: Window) -> Layout {
+		LayoutBuilder::default()
+			.screens(vec![ScreenBuilder::default()
+				.windows(vec![rule])
+				.build()
+				.unwrap()])
+			.build()
+			.unwrap()
+	}
+
+	#[test]
+	fn layout_positions_percentages_from_monitor() {
+		let live = WindowBuilder::default()
+			.process(Some("code".to_string()))
+			.build()
+			.unwrap();
+		let second = ScreenBuilder::default()
+			.id(1)
+			.bounds(Rect::new(1920, 0, 2560, 1440))
+			.windows(vec![live])
+			.build()
+			.unwrap();
+		let provider = FakeProvider::new(vec![ScreenBuilder::default().build().unwrap(), second]);
+		provider.layout(&layout(
+			WindowBuilder::default()
+				.process(Some("code".to_string()))
+				.x(Some("50%".to_string()))
+				.y(Some("0%".to_string()))
+				.w(Some("50%".to_string()))
+				.build()
+				.unwrap(),
+		));
+		let window = &provider.screens()[1].windows[0];
+		assert_eq!(Some("3200".to_string()), window.x);
+		assert_eq!(Some("0".to_string()), window.y);
+		assert_eq!(Some("1280".to_string()), window.w);
+	}
+
+	#[test]
+	fn layout_moves_to_desktop_by_index() {
+		let provider = provider();
+		provider.layout(&layout(
+			WindowBuilder::default()
+				.process(Some("slack".to_string()))
+				.desktop(Some(Desktop::Index(3)))
+				.build()
+				.unwrap(),
+		));
+		let screens = provider.screens();
+		assert_eq!(Some(Desktop::Index(0)), screens[0].windows[0].desktop);
+		assert_eq!(Some(Desktop::Index(3)), screens[0].windows[1].desktop);
+	}
+
+	#[test]
+	fn layout_moves_to_desktop_by_name() {
+		let provider = provider();
+		provider.layout(&layout(
+			WindowBuilder::default()
+				.process(Some("thunderbird".to_string()))
+				.desktop(Some(Desktop::from("comms")))
+				.build()
+				.unwrap(),
+		));
+		assert_eq!(
+			Some(Desktop::Index(3)),
+			provider.screens()[0].windows[0].desktop
+		);
+	}
+
+	#[test]
+	fn layout_ignores_unknown_desktop() {
+		let provider = provider();
+		provider.layout(&layout(
+			WindowBuilder::default()
+				.process(Some("slack".to_string()))
+				.desktop(Some(Desktop::from("games")))
+				.build()
+				.unwrap(),
+		));
+		assert_eq!(
+			Some(Desktop::Index(0)),
+			provider.screens()[0].windows[1].desktop
+		);
+	}
+
+	#[test]
+	fn layout_makes_sticky() {
+		let provider = provider();
+		provider.layout(&layout(
+			WindowBuilder::default()
+				.process(Some("slack".to_string()))
+				.desktop(Some(Desktop::Index(3)))
+				.sticky(Some(true))
+				.build()
+				.unwrap(),
+		));
+		let slack = &provider.screens()[0].windows[1];
+		assert_eq!(Some(true), slack.sticky);
+		assert_eq!(None, slack.desktop);
+	}
+
+	#[test]
+	fn layout_focuses_last_focused_rule() {
+		let provider = provider();
+		let rules = ["thunderbird", "slack"]
+			.iter()
+			.map(|p| {
+				WindowBuilder::default()
+					.process(Some(p.to_string()))
+					.focus(Some(true))
+					.build()
+					.unwrap()
+			})
+			.collect::<Vec<Window>>();
+		provider.layout(
+			&LayoutBuilder::default()
+				.screens(vec![ScreenBuilder::default()
+					.windows(rules)
+					.build()
+					.unwrap()])
+				.build()
+				.unwrap(),
+		);
+		assert_eq!(
+			Some("slack".to_string()),
+			provider.focused().and_then(|w| w.process)
+		);
+	}
+
+	#[test]
+	fn layout_without_focus_leaves_focus_alone() {
+		let provider = provider();
+		provider.layout(&layout(
+			WindowBuilder::default()
+				.process(Some("slack".to_string()))
+				.build()
+				.unwrap(),
+		));
+		assert_eq!(None, provider.focused());
+	}
+
+	#[test]
+	fn layout_resolves_percentages() {
+		let provider = provider().monitor(2000, 1000);
+		provider.layout(&layout(
+			WindowBuilder::default()
+				.process(Some("slack".to_string()))
+				.x(Some("50%".to_string()))
+				.h(Some("25%".to_string()))
+				.build()
+				.unwrap(),
+		));
+		let slack = &provider.screens()[0].windows[1];
+		assert_eq!(Some("1000".to_string()), slack.x);
+		assert_eq!(Some("250".to_string()), slack.h);
+	}
+
+	#[test]
+	fn layout_restores_maximized_window() {
+		let provider = provider();
+		let maximize = WindowBuilder::default()
+			.process(Some("slack".to_string()))
+			.maximized(Some(true))
+			.build()
+			.unwrap();
+		let mut journal = Journal::default();
+		journal.record(layout(maximize.clone()), &provider.screens());
+		provider.layout(&layout(maximize));
+		assert_eq!(Some(true), provider.screens()[0].windows[1].maximized);
+		provider.layout(&journal.undo(&provider.screens()).unwrap());
+		assert_eq!(None, provider.screens()[0].windows[1].maximized);
+	}
+}
@@ -6,3 +6,8 @@ pub use win::*;
 
 #[cfg(unix)]
 pub mod unix;
+
+#[cfg(unix)]
+pub use unix::*;
+
+pub mod fake;
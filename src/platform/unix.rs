@@ -1 +1,606 @@
+use crate::fingerprint::edid_fingerprint;
+use crate::layout::{Desktop, Layout, Length, Rect, Screen, ScreenBuilder, Window, WindowBuilder};
+use crate::WindowProvider;
 
+use std::path::PathBuf;
+use x11rb::connection::Connection;
+use x11rb::protocol::randr::{ConnectionExt as _, Rotation};
+use x11rb::protocol::xproto::{
+	AtomEnum, ClientMessageEvent, ConfigureWindowAux, ConnectionExt as _, EventMask, StackMode,
+};
+use x11rb::rust_connection::RustConnection;
+
+x11rb::atom_manager! {
+	pub Atoms: AtomsCookie {
+		EDID,
+		UTF8_STRING,
+		WM_CHANGE_STATE,
+		_NET_ACTIVE_WINDOW,
+		_NET_CLIENT_LIST,
+		_NET_CURRENT_DESKTOP,
+		_NET_DESKTOP_NAMES,
+		_NET_FRAME_EXTENTS,
+		_NET_NUMBER_OF_DESKTOPS,
+		_NET_WM_DESKTOP,
+		_NET_WM_NAME,
+		_NET_WM_PID,
+		_NET_WM_STATE,
+		_NET_WM_STATE_HIDDEN,
+		_NET_WM_STATE_MAXIMIZED_HORZ,
+		_NET_WM_STATE_MAXIMIZED_VERT,
+		_NET_WORKAREA,
+	}
+}
+
+type X11Result<T> = Result<T, Box<dyn std::error::Error>>;
+
+/// The `_NET_WM_DESKTOP` value of a window shown on all desktops.
+const ALL_DESKTOPS: u32 = 0xFFFFFFFF;
+/// The `_NET_WM_STATE` action removing a state from a window.
+const NET_WM_STATE_REMOVE: u32 = 0;
+/// The `_NET_WM_STATE` action adding a state to a window.
+const NET_WM_STATE_ADD: u32 = 1;
+/// The source indication of `_NET_WM_*` client messages sent by pagers and similar tools.
+const SOURCE_PAGER: u32 = 2;
+/// The `WM_CHANGE_STATE` value requesting a window be iconified.
+const ICONIC_STATE: u32 = 3;
+
+/// The bounds of a monitor in root window coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct X11Monitor {
+	pub x: i32,
+	pub y: i32,
+	pub width: i32,
+	pub height: i32,
+	/// The name of the monitor, which is the name of its output, e.g. `eDP-1`.
+	pub name: Option<String>,
+	/// The EDID fingerprint of the display connected to the output of the monitor.
+	pub edid: Option<String>,
+	/// Whether the output of the monitor is the primary output set by RandR.
+	pub primary: bool,
+	/// The physical dots per inch, if the display reports its size.
+	pub dpi: Option<u32>,
+	/// The clockwise rotation of the output of the monitor in degrees.
+	pub rotation: Option<u32>,
+}
+
+impl X11Monitor {
+	pub fn contains(&self, x: i32, y: i32) -> bool {
+		x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct X11Window {
+	pub id: u32,
+	pub monitor: usize,
+	pub window: Window,
+}
+
+impl std::fmt::Display for X11Window {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{:#?}", &self)
+	}
+}
+
+/// A connection to the X server along with the atoms needed to talk to an EWMH compliant window
+/// manager.
+pub struct X11Session {
+	conn: RustConnection,
+	root: u32,
+	atoms: Atoms,
+}
+
+impl X11Session {
+	pub fn connect() -> X11Result<Self> {
+		let (conn, screen_num) = x11rb::connect(None)?;
+		let root = conn.setup().roots[screen_num].root;
+		let atoms = Atoms::new(&conn)?.reply()?;
+		Ok(X11Session { conn, root, atoms })
+	}
+
+	/// Returns the connected monitors, falling back to the root window when RandR is unavailable.
+	pub fn monitors(&self) -> X11Result<Vec<X11Monitor>> {
+		if let Ok(reply) = self
+			.conn
+			.randr_get_monitors(self.root, true)
+			.map_err(Box::<dyn std::error::Error>::from)
+			.and_then(|cookie| Ok(cookie.reply()?))
+		{
+			if !reply.monitors.is_empty() {
+				return Ok(reply
+					.monitors
+					.iter()
+					.map(|m| X11Monitor {
+						x: m.x.into(),
+						y: m.y.into(),
+						width: m.width.into(),
+						height: m.height.into(),
+						name: self.atom_name(m.name),
+						edid: m.outputs.first().and_then(|output| self.edid(*output)),
+						primary: m.primary,
+						dpi: (m.width_in_millimeters > 0).then(|| {
+							(f64::from(m.width) * 25.4 / f64::from(m.width_in_millimeters)).round()
+								as u32
+						}),
+						rotation: m.outputs.first().and_then(|output| self.rotation(*output)),
+					})
+					.collect());
+			}
+		}
+		let geometry = self.conn.get_geometry(self.root)?.reply()?;
+		Ok(vec![X11Monitor {
+			x: 0,
+			y: 0,
+			width: geometry.width.into(),
+			height: geometry.height.into(),
+			name: None,
+			edid: None,
+			primary: true,
+			dpi: None,
+			rotation: None,
+		}])
+	}
+
+	fn atom_name(&self, atom: u32) -> Option<String> {
+		let reply = self.conn.get_atom_name(atom).ok()?.reply().ok()?;
+		String::from_utf8(reply.name).ok()
+	}
+
+	/// Returns the EDID fingerprint of the display connected to `output`, if it has an EDID.
+	fn edid(&self, output: u32) -> Option<String> {
+		let reply = self
+			.conn
+			.randr_get_output_property(output, self.atoms.EDID, AtomEnum::ANY, 0, 128, false, false)
+			.ok()?
+			.reply()
+			.ok()?;
+		edid_fingerprint(&reply.data)
+	}
+
+	/// Returns the clockwise rotation in degrees of the CRTC driving `output`, if it drives one.
+	fn rotation(&self, output: u32) -> Option<u32> {
+		let info = self
+			.conn
+			.randr_get_output_info(output, 0)
+			.ok()?
+			.reply()
+			.ok()?;
+		let crtc = self
+			.conn
+			.randr_get_crtc_info(info.crtc, 0)
+			.ok()?
+			.reply()
+			.ok()?;
+		let rotation = u16::from(crtc.rotation);
+		[
+			(Rotation::ROTATE0, 0),
+			(Rotation::ROTATE90, 90),
+			(Rotation::ROTATE180, 180),
+			(Rotation::ROTATE270, 270),
+		]
+		.iter()
+		.find(|(flag, _)| rotation & u16::from(*flag) != 0)
+		.map(|(_, degrees)| *degrees)
+	}
+
+	/// Returns the area of the root window not taken by panels on the current desktop, which
+	/// spans every monitor.
+	pub fn work_area(&self) -> X11Result<Option<Rect>> {
+		let current = self
+			.property32(
+				self.root,
+				self.atoms._NET_CURRENT_DESKTOP,
+				AtomEnum::CARDINAL,
+			)?
+			.first()
+			.copied()
+			.unwrap_or(0) as usize;
+		let areas = self.property32(self.root, self.atoms._NET_WORKAREA, AtomEnum::CARDINAL)?;
+		Ok(areas
+			.chunks_exact(4)
+			.nth(current)
+			.or_else(|| areas.chunks_exact(4).next())
+			.map(|area| {
+				Rect::new(
+					area[0] as i32,
+					area[1] as i32,
+					area[2] as i32,
+					area[3] as i32,
+				)
+			}))
+	}
+
+	/// Returns the names of the virtual desktops, using the index of a desktop when the window
+	/// manager does not name it.
+	pub fn desktop_names(&self) -> X11Result<Vec<String>> {
+		let count = self
+			.property32(
+				self.root,
+				self.atoms._NET_NUMBER_OF_DESKTOPS,
+				AtomEnum::CARDINAL,
+			)?
+			.first()
+			.copied()
+			.unwrap_or(0);
+		let names = self
+			.conn
+			.get_property(
+				false,
+				self.root,
+				self.atoms._NET_DESKTOP_NAMES,
+				self.atoms.UTF8_STRING,
+				0,
+				u32::MAX,
+			)?
+			.reply()?
+			.value;
+		let mut names = names
+			.split(|b| *b == 0)
+			.map(|name| String::from_utf8_lossy(name).into_owned())
+			.take(count as usize)
+			.collect::<Vec<String>>();
+		while names.len() < count as usize {
+			names.push(names.len().to_string());
+		}
+		Ok(names)
+	}
+
+	/// Returns the windows managed by the window manager.
+	pub fn windows(&self, monitors: &[X11Monitor]) -> X11Result<Vec<X11Window>> {
+		let mut windows = Vec::new();
+		for id in self.property32(self.root, self.atoms._NET_CLIENT_LIST, AtomEnum::WINDOW)? {
+			// A window closed while listing the others is left out rather than failing the listing.
+			match self.window(id, monitors) {
+				Ok(window) => windows.push(window),
+				Err(e) => log::warn!("Skipping window {}: {}", id, e),
+			}
+		}
+		Ok(windows)
+	}
+
+	fn window(&self, id: u32, monitors: &[X11Monitor]) -> X11Result<X11Window> {
+		let (x, y, w, h) = self.rect(id)?;
+		let monitor = monitors
+			.iter()
+			.position(|m| m.contains(x + w / 2, y + h / 2))
+			.unwrap_or(0);
+		let desktop = self
+			.property32(id, self.atoms._NET_WM_DESKTOP, AtomEnum::CARDINAL)?
+			.first()
+			.copied();
+		let pid = self
+			.property32(id, self.atoms._NET_WM_PID, AtomEnum::CARDINAL)?
+			.first()
+			.copied();
+		let states = self.property32(id, self.atoms._NET_WM_STATE, AtomEnum::ATOM)?;
+		let vertical = states.contains(&self.atoms._NET_WM_STATE_MAXIMIZED_VERT);
+		let horizontal = states.contains(&self.atoms._NET_WM_STATE_MAXIMIZED_HORZ);
+		let window = WindowBuilder::default()
+			.id(Some(u64::from(id)))
+			.pid(pid)
+			.title(self.title(id)?)
+			.process(pid.map(process).unwrap_or_default())
+			.x(x.to_string())
+			.y(y.to_string())
+			.w(w.to_string())
+			.h(h.to_string())
+			.desktop(desktop.filter(|d| *d != ALL_DESKTOPS).map(Desktop::Index))
+			.sticky(desktop.filter(|d| *d == ALL_DESKTOPS).map(|_| true))
+			.maximized((vertical && horizontal).then_some(true))
+			.maximized_vertical((vertical && !horizontal).then_some(true))
+			.maximized_horizontal((horizontal && !vertical).then_some(true))
+			.minimized(
+				states
+					.contains(&self.atoms._NET_WM_STATE_HIDDEN)
+					.then_some(true),
+			)
+			.build()
+			.unwrap();
+		Ok(X11Window {
+			id,
+			monitor,
+			window,
+		})
+	}
+
+	fn property32(&self, window: u32, property: u32, type_: AtomEnum) -> X11Result<Vec<u32>> {
+		let reply = self
+			.conn
+			.get_property(false, window, property, type_, 0, u32::MAX)?
+			.reply()?;
+		Ok(reply
+			.value32()
+			.map(|values| values.collect())
+			.unwrap_or_default())
+	}
+
+	fn title(&self, window: u32) -> X11Result<String> {
+		let reply = self
+			.conn
+			.get_property(
+				false,
+				window,
+				self.atoms._NET_WM_NAME,
+				self.atoms.UTF8_STRING,
+				0,
+				u32::MAX,
+			)?
+			.reply()?;
+		if !reply.value.is_empty() {
+			return Ok(String::from_utf8_lossy(&reply.value).into_owned());
+		}
+		let reply = self
+			.conn
+			.get_property(
+				false,
+				window,
+				AtomEnum::WM_NAME,
+				AtomEnum::STRING,
+				0,
+				u32::MAX,
+			)?
+			.reply()?;
+		Ok(reply.value.iter().map(|b| char::from(*b)).collect())
+	}
+
+	fn rect(&self, window: u32) -> X11Result<(i32, i32, i32, i32)> {
+		let geometry = self.conn.get_geometry(window)?.reply()?;
+		let origin = self
+			.conn
+			.translate_coordinates(window, self.root, 0, 0)?
+			.reply()?;
+		// `ConfigureWindow` places the frame of a window decorated by the window manager, so the
+		// position is that of the frame, left of and above the client area by its extents.
+		let extents = self.property32(window, self.atoms._NET_FRAME_EXTENTS, AtomEnum::CARDINAL)?;
+		let (left, top) = match extents[..] {
+			[left, _, top, _] => (left as i32, top as i32),
+			_ => (0, 0),
+		};
+		Ok((
+			i32::from(origin.dst_x) - left,
+			i32::from(origin.dst_y) - top,
+			geometry.width.into(),
+			geometry.height.into(),
+		))
+	}
+
+	fn send_client_message(&self, window: u32, type_: u32, data: [u32; 5]) -> X11Result<()> {
+		let event = ClientMessageEvent::new(32, window, type_, data);
+		self.conn.send_event(
+			false,
+			self.root,
+			EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+			event,
+		)?;
+		Ok(())
+	}
+
+	/// Moves, resizes and changes the state of `window` as specified by `rule`.
+	pub fn update(
+		&self,
+		window: &X11Window,
+		rule: &Window,
+		monitor: &X11Monitor,
+		desktops: &[String],
+	) -> X11Result<()> {
+		let resolve =
+			|value: &Option<String>, origin: i32, extent: i32| -> X11Result<Option<i32>> {
+				match value {
+					Some(value) => Ok(Some(value.parse::<Length>()?.to_position(origin, extent))),
+					None => Ok(None),
+				}
+			};
+		let mut aux = ConfigureWindowAux::new();
+		if let Some(x) = resolve(&rule.x, monitor.x, monitor.width)? {
+			aux = aux.x(x);
+		}
+		if let Some(y) = resolve(&rule.y, monitor.y, monitor.height)? {
+			aux = aux.y(y);
+		}
+		if let Some(w) = resolve(&rule.w, 0, monitor.width)? {
+			aux = aux.width(w.max(1) as u32);
+		}
+		if let Some(h) = resolve(&rule.h, 0, monitor.height)? {
+			aux = aux.height(h.max(1) as u32);
+		}
+		log::trace!("x11::ConfigureWindow -- {:?} for {}", aux, window.id);
+		self.conn.configure_window(window.id, &aux)?;
+
+		if rule.sticky == Some(true) {
+			log::trace!("x11::_NET_WM_DESKTOP all for {}", window.id);
+			self.send_client_message(
+				window.id,
+				self.atoms._NET_WM_DESKTOP,
+				[ALL_DESKTOPS, SOURCE_PAGER, 0, 0, 0],
+			)?;
+		} else if let Some(desktop) = &rule.desktop {
+			match desktop.resolve(desktops) {
+				Some(index) => {
+					log::trace!("x11::_NET_WM_DESKTOP {} for {}", index, window.id);
+					self.send_client_message(
+						window.id,
+						self.atoms._NET_WM_DESKTOP,
+						[index, SOURCE_PAGER, 0, 0, 0],
+					)?;
+				}
+				None => log::warn!("No such desktop: {}", desktop),
+			}
+		}
+
+		// `maximized_vertical` and `maximized_horizontal` keep a window maximized in that direction
+		// even where `maximized` is false.
+		let mut added = Vec::new();
+		let mut removed = Vec::new();
+		for (state, axis) in [
+			(
+				rule.maximized_vertical,
+				self.atoms._NET_WM_STATE_MAXIMIZED_VERT,
+			),
+			(
+				rule.maximized_horizontal,
+				self.atoms._NET_WM_STATE_MAXIMIZED_HORZ,
+			),
+		] {
+			match (rule.maximized, state) {
+				(Some(true), _) | (_, Some(true)) => added.push(axis),
+				(Some(false), _) | (_, Some(false)) => removed.push(axis),
+				(None, None) => {}
+			}
+		}
+		for (action, mut states) in [(NET_WM_STATE_REMOVE, removed), (NET_WM_STATE_ADD, added)] {
+			if !states.is_empty() {
+				states.resize(2, 0);
+				self.send_client_message(
+					window.id,
+					self.atoms._NET_WM_STATE,
+					[action, states[0], states[1], SOURCE_PAGER, 0],
+				)?;
+			}
+		}
+		if rule.minimized == Some(true) {
+			self.send_client_message(
+				window.id,
+				self.atoms.WM_CHANGE_STATE,
+				[ICONIC_STATE, 0, 0, 0, 0],
+			)?;
+		} else if rule.minimized == Some(false) && window.window.minimized == Some(true) {
+			// Mapping an iconified window asks the window manager to show it again.
+			log::trace!("x11::MapWindow for {}", window.id);
+			self.conn.map_window(window.id)?;
+		}
+		Ok(())
+	}
+
+	/// Raises `window` and asks the window manager to activate it.
+	pub fn activate(&self, window: &X11Window) -> X11Result<()> {
+		log::trace!("x11::_NET_ACTIVE_WINDOW for {}", window.id);
+		self.conn.configure_window(
+			window.id,
+			&ConfigureWindowAux::new().stack_mode(StackMode::ABOVE),
+		)?;
+		self.send_client_message(
+			window.id,
+			self.atoms._NET_ACTIVE_WINDOW,
+			[SOURCE_PAGER, x11rb::CURRENT_TIME, 0, 0, 0],
+		)
+	}
+
+	pub fn flush(&self) -> X11Result<()> {
+		self.conn.flush()?;
+		Ok(())
+	}
+}
+
+/// Returns the path of the executable of the process `pid`, if it can be read.
+fn process(pid: u32) -> String {
+	std::fs::read_link(PathBuf::from(format!("/proc/{}/exe", pid)))
+		.map(|path| path.display().to_string())
+		.unwrap_or_default()
+}
+
+#[derive(Debug, Default)]
+pub struct X11Provider;
+
+impl X11Provider {
+	fn try_screens(&self) -> X11Result<Vec<Screen>> {
+		let session = X11Session::connect()?;
+		let monitors = session.monitors()?;
+		// The work area spans every monitor, so each monitor gets its share of it, which leaves
+		// out the panels along the outer edges of the monitors.
+		let work_area = session.work_area().unwrap_or_default();
+		let mut screens = monitors
+			.iter()
+			.enumerate()
+			.map(|(id, m)| {
+				let bounds = Rect::new(m.x, m.y, m.width, m.height);
+				ScreenBuilder::default()
+					.id(id as u8)
+					.bounds(bounds)
+					.name(m.name.clone())
+					.edid(m.edid.clone())
+					.work_area(work_area.and_then(|area| bounds.intersection(&area)))
+					.primary(Some(m.primary))
+					.dpi(m.dpi)
+					.rotation(m.rotation)
+					.build()
+					.unwrap()
+			})
+			.collect::<Vec<Screen>>();
+		for window in session.windows(&monitors)? {
+			log::debug!("Window {}", window);
+			screens[window.monitor].windows.push(window.window);
+		}
+		Ok(screens)
+	}
+
+	fn try_layout(&self, layout: &Layout) -> X11Result<()> {
+		let session = X11Session::connect()?;
+		let monitors = session.monitors()?;
+		let desktops = session.desktop_names()?;
+		let windows = session.windows(&monitors)?;
+		let mut focused = None;
+		for rule in layout.screens.iter().flat_map(|s| &s.windows) {
+			if let Some(window) = windows.iter().find(|w| rule.matches(&w.window)) {
+				if let Err(e) = session.update(window, rule, &monitors[window.monitor], &desktops) {
+					log::error!("Failed to update window {}: {}", window.id, e);
+				}
+				if rule.focus == Some(true) {
+					focused = Some(window);
+				}
+			}
+		}
+		if let Some(window) = focused {
+			session.activate(window)?;
+		}
+		session.flush()
+	}
+}
+
+impl WindowProvider for X11Provider {
+	fn screens(&self) -> Vec<Screen> {
+		self.try_screens().unwrap_or_else(|e| {
+			log::warn!("Failed to list X11 windows: {}", e);
+			Vec::new()
+		})
+	}
+
+	fn layout(&self, layout: &Layout) {
+		if let Err(e) = self.try_layout(layout) {
+			log::warn!("Failed to apply layout on X11: {}", e);
+		}
+	}
+
+	fn desktop_names(&self) -> Vec<String> {
+		X11Session::connect()
+			.and_then(|session| session.desktop_names())
+			.unwrap_or_else(|e| {
+				log::warn!("Failed to list X11 desktops: {}", e);
+				Vec::new()
+			})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn monitor_contains() {
+		let monitor = X11Monitor {
+			x: 1920,
+			y: 0,
+			width: 2560,
+			height: 1440,
+			name: None,
+			edid: None,
+			primary: false,
+			dpi: None,
+			rotation: None,
+		};
+		assert!(monitor.contains(1920, 0));
+		assert!(monitor.contains(4479, 1439));
+		assert!(!monitor.contains(4480, 0));
+		assert!(!monitor.contains(1919, 0));
+	}
+}
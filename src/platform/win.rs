@@ -1,7 +1,7 @@
 use crate::{Point, WindowProvider};
 
 use crate::layout::{
-	Layout, Screen, ScreenBuilder, Window, WindowBuilder, MAX_WINDOW_TITLE_LENGTH,
+	Layout, Length, Rect, Screen, ScreenBuilder, Window, WindowBuilder, MAX_WINDOW_TITLE_LENGTH,
 };
 
 #[cfg(windows)]
@@ -24,6 +24,8 @@ use winapi::um::dwmapi::{DwmGetWindowAttribute, DWMWA_CLOAKED};
 use winapi::um::handleapi::CloseHandle;
 use winapi::um::processthreadsapi::OpenProcess;
 use winapi::um::psapi::GetModuleFileNameExW;
+use winapi::um::shellscalingapi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI};
+use winapi::um::wingdi::DEVMODEW;
 // use winapi::um::winbase::{
 // 	FormatMessageW, FORMAT_MESSAGE_ARGUMENT_ARRAY, FORMAT_MESSAGE_FROM_SYSTEM,
 // 	FORMAT_MESSAGE_IGNORE_INSERTS,
@@ -31,11 +33,13 @@ use winapi::um::psapi::GetModuleFileNameExW;
 use winapi::um::winnt::HANDLE;
 use winapi::um::winnt::{PROCESS_QUERY_INFORMATION, PROCESS_VM_READ};
 use winapi::um::winuser::{
-	BeginDeferWindowPos, DeferWindowPos, EndDeferWindowPos, EnumDisplayMonitors, EnumWindows,
-	GetMonitorInfoW, GetWindowLongPtrW, GetWindowRect, GetWindowTextW, GetWindowThreadProcessId,
-	IsWindowVisible, MonitorFromWindow, ShowWindow, GWL_EXSTYLE, HDWP, MONITORINFOEXW,
-	MONITOR_DEFAULTTOPRIMARY, SWP_NOACTIVATE, SWP_NOMOVE, SWP_NOOWNERZORDER, SWP_NOSIZE,
-	SWP_NOZORDER, SW_SHOWMAXIMIZED, SW_SHOWMINIMIZED, WM_NULL, WS_EX_TOOLWINDOW, WS_EX_WINDOWEDGE,
+	BeginDeferWindowPos, DeferWindowPos, EndDeferWindowPos, EnumDisplayMonitors,
+	EnumDisplaySettingsW, EnumWindows, GetMonitorInfoW, GetWindowLongPtrW, GetWindowRect,
+	GetWindowTextW, GetWindowThreadProcessId, IsIconic, IsWindowVisible, IsZoomed,
+	MonitorFromWindow, SetForegroundWindow, ShowWindow, ENUM_CURRENT_SETTINGS, GWL_EXSTYLE, HDWP,
+	MONITORINFOEXW, MONITORINFOF_PRIMARY, MONITOR_DEFAULTTOPRIMARY, SWP_NOACTIVATE, SWP_NOMOVE,
+	SWP_NOOWNERZORDER, SWP_NOSIZE, SWP_NOZORDER, SW_RESTORE, SW_SHOWMAXIMIZED, SW_SHOWMINIMIZED,
+	WM_NULL, WS_EX_TOOLWINDOW, WS_EX_WINDOWEDGE,
 };
 
 pub struct Rectangle(RECT);
@@ -177,12 +181,16 @@ impl Win32Window {
 			hwnd,
 			monitor: monitor,
 			window: WindowBuilder::default()
+				.id(Some(hwnd as u64))
+				.pid(Some(property::get_pid(hwnd)))
 				.title(title)
 				.process(process)
 				.x(origin.x.to_string())
 				.y(origin.y.to_string())
 				.w(rect.width().to_string())
 				.h(rect.height().to_string())
+				.maximized(unsafe { IsZoomed(hwnd) != 0 }.then_some(true))
+				.minimized(unsafe { IsIconic(hwnd) != 0 }.then_some(true))
 				.build()
 				.unwrap(),
 		}
@@ -192,7 +200,10 @@ impl Win32Window {
 	pub fn update(&self, hdwp: &mut HDWP) {
 		let rect = property::get_rect(self.hwnd);
 		let origin = rect.origin();
-		let mut flags = SWP_NOZORDER | SWP_NOOWNERZORDER | SWP_NOACTIVATE;
+		let mut flags = SWP_NOZORDER | SWP_NOOWNERZORDER;
+		if self.window.focus != Some(true) {
+			flags |= SWP_NOACTIVATE;
+		}
 		if self.window.x.is_none() && self.window.y.is_none() {
 			flags |= SWP_NOMOVE;
 		}
@@ -210,30 +221,29 @@ impl Win32Window {
 		// TODO match Windows with maximize to maximize
 		// TODO match Windows with maximize_horizontal to maximize_horizontal
 		// TODO match Windows with maximize_vertical to maximize_vertical
-		let pixels_x = if let Some(x_str) = &self.window.x {
-			// Pixels::from_str(&x_str).unwrap().count
-			into_pixels(x_str, &monitor_info)
-		} else {
-			origin.x
-		};
-		let pixels_y = if let Some(y_str) = &self.window.y {
-			// Pixels::from_str(&y_str).unwrap().count
-			into_pixels(y_str, &monitor_info)
-		} else {
-			origin.y
-		};
-		let pixels_w = if let Some(w_str) = &self.window.w {
-			// Pixels::from_str(&w_str).unwrap().count
-			into_pixels(w_str, &monitor_info)
-		} else {
-			rect.width()
-		};
-		let pixels_h = if let Some(h_str) = &self.window.h {
-			// Pixels::from_str(&h_str).unwrap().count
-			into_pixels(h_str, &monitor_info)
-		} else {
-			rect.height()
+		// Percentages position the window from the top left corner of the monitor, and size it by
+		// its width and height.
+		let work_origin = monitor_info.origin();
+		let position = |value: &Option<String>, origin: i32, extent: i32, current: i32| {
+			value
+				.as_ref()
+				.and_then(|v| v.parse::<Length>().ok())
+				.map_or(current, |length| length.to_position(origin, extent))
 		};
+		let pixels_x = position(
+			&self.window.x,
+			work_origin.x,
+			monitor_info.width(),
+			origin.x,
+		);
+		let pixels_y = position(
+			&self.window.y,
+			work_origin.y,
+			monitor_info.height(),
+			origin.y,
+		);
+		let pixels_w = position(&self.window.w, 0, monitor_info.width(), rect.width());
+		let pixels_h = position(&self.window.h, 0, monitor_info.height(), rect.height());
 		if let Some(title) = &self.window.title {
 			log::trace!("winapi::DeferWindowPos -- {} for \"{}\"", rect, title);
 		} else if let Some(process) = &self.window.process {
@@ -251,18 +261,31 @@ impl Win32Window {
 				flags,
 			)
 		};
-		if self.window.minimized.is_some() {
+		let zoomed = unsafe { IsZoomed(self.hwnd) != 0 };
+		let iconic = unsafe { IsIconic(self.hwnd) != 0 };
+		if (self.window.maximized == Some(false) && zoomed)
+			|| (self.window.minimized == Some(false) && iconic)
+		{
+			log::trace!("winapi::ShowWindow restored");
+			unsafe {
+				ShowWindow(self.hwnd, SW_RESTORE);
+			}
+		}
+		if self.window.minimized == Some(true) {
 			log::trace!("winapi::ShowWindow minimized");
 			unsafe {
 				ShowWindow(self.hwnd, SW_SHOWMINIMIZED);
 			}
 		}
-		if self.window.maximized.is_some() {
+		if self.window.maximized == Some(true) {
 			log::trace!("winapi::ShowWindow maximized");
 			unsafe {
 				ShowWindow(self.hwnd, SW_SHOWMAXIMIZED);
 			}
 		}
+		if self.window.desktop.is_some() || self.window.sticky.is_some() {
+			log::warn!("Moving windows between virtual desktops is not supported on Windows");
+		}
 		if *hdwp == NULL {
 			log::error!(
 				"winapi::DeferWindowPos error: {}",
@@ -276,6 +299,16 @@ impl Win32Window {
 		self.update(hdwp);
 	}
 
+	/// Brings the window to the foreground and activates it.
+	///
+	/// See https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-setforegroundwindow
+	pub fn activate(&self) {
+		log::trace!("winapi::SetForegroundWindow");
+		if unsafe { SetForegroundWindow(self.hwnd) } == 0 {
+			log::warn!("winapi::SetForegroundWindow refused to activate the window");
+		}
+	}
+
 	#[allow(dead_code)]
 	pub fn get_rect(&self) -> Rectangle {
 		property::get_rect(self.hwnd)
@@ -348,6 +381,56 @@ impl Win32Monitor {
 		};
 		name.into_string().unwrap()
 	}
+
+	/// Returns the bounds of the monitor.
+	pub fn bounds(&self) -> Rect {
+		let rc = self.info.rcMonitor;
+		Rect::new(rc.left, rc.top, rc.right - rc.left, rc.bottom - rc.top)
+	}
+
+	/// Returns the part of the monitor not taken by the task bar and docked tool bars.
+	pub fn work_area(&self) -> Rect {
+		let rc = self.info.rcWork;
+		Rect::new(rc.left, rc.top, rc.right - rc.left, rc.bottom - rc.top)
+	}
+
+	/// Returns whether the monitor is the primary one, which has its top left corner at the origin.
+	pub fn is_primary(&self) -> bool {
+		self.info.dwFlags & MONITORINFOF_PRIMARY != 0
+	}
+
+	/// Returns the dots per inch windows are scaled for on the monitor, 96 being unscaled.
+	///
+	/// See https://learn.microsoft.com/en-us/windows/win32/api/shellscalingapi/nf-shellscalingapi-getdpiformonitor
+	pub fn dpi(&self) -> Option<u32> {
+		let (mut dpi_x, mut dpi_y) = (0, 0);
+		let result =
+			unsafe { GetDpiForMonitor(self.hmonitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) };
+		log::trace!("winapi::GetDpiForMonitor returned {} ({})", dpi_x, result);
+		(result >= 0).then_some(dpi_x)
+	}
+
+	/// Returns the clockwise rotation of the monitor in degrees.
+	pub fn rotation(&self) -> Option<u32> {
+		let mut mode: DEVMODEW = unsafe { mem::zeroed() };
+		mode.dmSize = mem::size_of::<DEVMODEW>() as u16;
+		let result = unsafe {
+			EnumDisplaySettingsW(
+				self.info.szDevice.as_ptr(),
+				ENUM_CURRENT_SETTINGS,
+				&mut mode,
+			)
+		};
+		if result == 0 {
+			log::warn!(
+				"winapi::EnumDisplaySettingsW error: {}",
+				std::io::Error::last_os_error()
+			);
+			return None;
+		}
+		// DMDO_DEFAULT, DMDO_90, DMDO_180 and DMDO_270 count quarter turns clockwise.
+		Some(unsafe { mode.u1.s2().dmDisplayOrientation } * 90)
+	}
 }
 
 impl From<HMONITOR> for Win32Monitor {
@@ -536,6 +619,12 @@ pub mod property {
 		title
 	}
 
+	pub fn get_pid(hwnd: HWND) -> u32 {
+		let mut proc_id: DWORD = 0;
+		unsafe { GetWindowThreadProcessId(hwnd, &mut proc_id) };
+		proc_id
+	}
+
 	pub fn get_process(hwnd: HWND) -> String {
 		let mut proc_id: DWORD = 0;
 		let mut window_process: [WCHAR; MAX_PATH] = [0; MAX_PATH];
@@ -633,6 +722,9 @@ fn find_match_by_process(
 }
 
 fn find_match(windows: &Vec<Win32Window>, win: &Window) -> Option<PlatformWindow> {
+	if let Some(id) = win.id {
+		return windows.iter().find(|w| w.window.id == Some(id)).cloned();
+	}
 	match (&win.title, &win.process) {
 		(Some(title_regex), Some(process_regex)) => {
 			match (Regex::new(title_regex), Regex::new(process_regex)) {
@@ -662,7 +754,18 @@ impl WindowProvider for Win32Provider {
 		let mut screen_map = HashMap::new();
 		let mut screen_count = 0;
 		for win32monitor in list_monitors() {
-			let screen = ScreenBuilder::default().id(screen_count).build().unwrap();
+			let dpi = win32monitor.dpi();
+			let screen = ScreenBuilder::default()
+				.id(screen_count)
+				.bounds(win32monitor.bounds())
+				.name(Some(win32monitor.title()))
+				.work_area(Some(win32monitor.work_area()))
+				.primary(Some(win32monitor.is_primary()))
+				.dpi(dpi)
+				.scale(dpi.map(|dpi| (f64::from(dpi) / 96.0 * 100.0).round() / 100.0))
+				.rotation(win32monitor.rotation())
+				.build()
+				.unwrap();
 			screen_map.insert(win32monitor.hmonitor, screen);
 			log::debug!("Screen {}", win32monitor.hmonitor as i32);
 			screen_count += 1;
@@ -673,16 +776,7 @@ impl WindowProvider for Win32Provider {
 				log::debug!("Window {}", window);
 				if let Some(screen) = screen_map.get_mut(&window.monitor) {
 					log::debug!("HERE {}", screen);
-					let window = WindowBuilder::default()
-						.title(window.window.title)
-						.process(window.window.process)
-						.x(window.window.x)
-						.y(window.window.y)
-						.w(window.window.w)
-						.h(window.window.h)
-						.build()
-						.unwrap();
-					screen.windows.push(window);
+					screen.windows.push(window.window);
 				}
 			}
 		}
@@ -697,10 +791,14 @@ impl WindowProvider for Win32Provider {
 			hdwp = BeginDeferWindowPos(1);
 		}
 
+		let mut focused = None;
 		for s in &layout.screens {
 			for layout_window in &s.windows {
 				if let Some(mut win32window) = find_match(&windows, &layout_window) {
 					win32window.layout(&mut hdwp, &layout_window);
+					if layout_window.focus == Some(true) {
+						focused = Some(win32window);
+					}
 				}
 			}
 		}
@@ -710,6 +808,9 @@ impl WindowProvider for Win32Provider {
 				EndDeferWindowPos(hdwp);
 			}
 		}
+		if let Some(win32window) = focused {
+			win32window.activate();
+		}
 	}
 }
 
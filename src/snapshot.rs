@@ -0,0 +1,261 @@
+use crate::fingerprint::DisplayMatcher;
+use crate::layout::{Layout, Screen, Window, LAYOUT_VERSION};
+
+/// Titles of desktop shell windows, which are listed like any other window but never worth
+/// moving.
+static SHELL_TITLES: [&str; 2] = ["Desktop", "Program Manager"];
+
+/// The smallest width and height of a window worth moving, in pixels. Smaller windows are
+/// typically hidden helper windows, e.g. of tray icons.
+pub const MIN_WINDOW_SIZE: i32 = 32;
+
+/// Returns the name of the executable of `process`, e.g. `firefox` for
+/// `/usr/lib/firefox/firefox`, with either kind of path separator.
+///
+/// # Examples
+///
+/// ```
+/// # use wlm::snapshot::process_name;
+/// assert_eq!("firefox", process_name("/usr/lib/firefox/firefox"));
+/// assert_eq!("chrome.exe", process_name(r"C:\Program Files\Google\Chrome\chrome.exe"));
+/// assert_eq!("code", process_name("code"));
+/// ```
+pub fn process_name(process: &str) -> &str {
+	process.rsplit(['/', '\\']).next().unwrap_or(process)
+}
+
+/// Returns whether `window`, as listed by `WindowProvider::screens`, is not worth a rule: it has
+/// no title, is too small to be a real window or belongs to the desktop shell.
+pub fn is_junk(window: &Window) -> bool {
+	let title = window.title.as_deref().unwrap_or("").trim();
+	let too_small = |length: &Option<String>| {
+		length
+			.as_deref()
+			.and_then(|l| l.parse::<i32>().ok())
+			.is_some_and(|l| l < MIN_WINDOW_SIZE)
+	};
+	title.is_empty()
+		|| SHELL_TITLES.contains(&title)
+		|| too_small(&window.w)
+		|| too_small(&window.h)
+}
+
+/// Returns a rule matching `window`, as listed by `WindowProvider::screens`, and moving it back
+/// to where it is.
+///
+/// The title is escaped and anchored, so it only matches that exact title, and the process is
+/// reduced to the name of its executable.
+///
+/// # Examples
+///
+/// ```
+/// # use wlm::layout::WindowBuilder;
+/// # use wlm::snapshot::rule_for;
+/// let window = WindowBuilder::default()
+///     .title(Some("main.rs (wlm) - Code".to_string()))
+///     .process(Some("/usr/share/code/code".to_string()))
+///     .build()
+///     .unwrap();
+/// let rule = rule_for(&window);
+/// assert_eq!(Some(r"^main\.rs \(wlm\) \- Code$".to_string()), rule.title);
+/// assert_eq!(Some("code".to_string()), rule.process);
+/// assert!(rule.matches(&window));
+/// ```
+pub fn rule_for(window: &Window) -> Window {
+	Window {
+		title: window
+			.title
+			.as_ref()
+			.map(|title| format!("^{}$", regex::escape(title))),
+		process: window
+			.process
+			.as_ref()
+			.map(|process| regex::escape(process_name(process))),
+		x: window.x.clone(),
+		y: window.y.clone(),
+		w: window.w.clone(),
+		h: window.h.clone(),
+		desktop: window.desktop.clone(),
+		sticky: window.sticky,
+		..Window::new()
+	}
+}
+
+/// Returns a layout moving the windows on `screens`, as listed by `WindowProvider::screens`,
+/// back to where they are, skipping junk windows, see `is_junk`.
+///
+/// Rules are grouped by the screen their window is on, along with what is known about its monitor,
+/// and the layout matches the connected displays, so `wlm layout --auto` picks it when they are
+/// connected again. With `relative`, the position and size of each window is written in percent
+/// of its monitor.
+pub fn snapshot(screens: &[Screen], relative: bool) -> Layout {
+	let match_displays = screens
+		.iter()
+		.filter_map(|screen| match (&screen.edid, &screen.name) {
+			(Some(edid), _) => Some(DisplayMatcher {
+				edid: Some(edid.clone()),
+				..DisplayMatcher::default()
+			}),
+			(None, Some(name)) => Some(DisplayMatcher {
+				name: Some(name.clone()),
+				..DisplayMatcher::default()
+			}),
+			(None, None) => None,
+		})
+		.collect::<Vec<DisplayMatcher>>();
+	// Only when every display can be told apart, as a partial list would fit other setups.
+	let match_displays = if match_displays.len() == screens.len() {
+		match_displays
+	} else {
+		Vec::new()
+	};
+	let mut rules: Vec<Window> = Vec::new();
+	let screens = screens
+		.iter()
+		.map(|screen| {
+			let mut windows = Vec::new();
+			for window in screen.windows.iter().filter(|w| !is_junk(w)) {
+				let rule = rule_for(window);
+				if rules
+					.iter()
+					.any(|r| r.title == rule.title && r.process == rule.process)
+				{
+					continue;
+				}
+				rules.push(rule.clone());
+				windows.push(match (relative, &screen.bounds) {
+					(true, Some(bounds)) => rule.relative_to(bounds),
+					_ => rule,
+				});
+			}
+			// The monitor is described as listed, which applying the layout ignores.
+			Screen {
+				windows,
+				..screen.clone()
+			}
+		})
+		.filter(|screen| !screen.windows.is_empty())
+		.collect();
+	Layout {
+		version: Some(LAYOUT_VERSION),
+		match_displays,
+		screens,
+		..Layout::new()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::layout::{Rect, ScreenBuilder, WindowBuilder};
+
+	fn window(title: &str, process: &str, w: i32, h: i32) -> Window {
+		WindowBuilder::default()
+			.title(Some(title.to_string()))
+			.process(Some(process.to_string()))
+			.x(Some("1920".to_string()))
+			.y(Some("0".to_string()))
+			.w(Some(w.to_string()))
+			.h(Some(h.to_string()))
+			.build()
+			.unwrap()
+	}
+
+	fn screens() -> Vec<Screen> {
+		let screen = |id: u8, x: i32, edid: Option<&str>, windows: Vec<Window>| {
+			ScreenBuilder::default()
+				.id(id)
+				.bounds(Rect::new(x, 0, 1920, 1080))
+				.name(Some(format!("DP-{}", id)))
+				.edid(edid.map(String::from))
+				.windows(windows)
+				.build()
+				.unwrap()
+		};
+		vec![
+			screen(
+				0,
+				0,
+				Some("10ac5aa04c303531"),
+				vec![window("Desktop", "/usr/bin/nautilus", 1920, 1080)],
+			),
+			screen(
+				1,
+				1920,
+				None,
+				vec![
+					window(
+						"Inbox (3) - Thunderbird",
+						"/usr/lib/thunderbird/thunderbird",
+						960,
+						1080,
+					),
+					window("", "/usr/bin/plasmashell", 960, 1080),
+					window("tray", "/usr/bin/nm-applet", 1, 1),
+					window(
+						"Inbox (3) - Thunderbird",
+						"/usr/lib/thunderbird/thunderbird",
+						960,
+						1080,
+					),
+				],
+			),
+		]
+	}
+
+	#[test]
+	fn junk() {
+		assert!(is_junk(&window("", "plasmashell", 1920, 1080)));
+		assert!(is_junk(&window(
+			"Program Manager",
+			"explorer.exe",
+			1920,
+			1080
+		)));
+		assert!(is_junk(&window("tray", "nm-applet", 24, 24)));
+		assert!(!is_junk(&window("Inbox", "thunderbird", 960, 1080)));
+	}
+
+	#[test]
+	fn snapshot_skips_junk_and_duplicates() {
+		let layout = snapshot(&screens(), false);
+		assert_eq!(1, layout.screens.len());
+		assert_eq!(Some(1), layout.screens[0].id);
+		assert_eq!(
+			Some(Rect::new(1920, 0, 1920, 1080)),
+			layout.screens[0].bounds
+		);
+		let rules = &layout.screens[0].windows;
+		assert_eq!(1, rules.len());
+		assert_eq!(
+			Some(r"^Inbox \(3\) \- Thunderbird$".to_string()),
+			rules[0].title
+		);
+		assert_eq!(Some("thunderbird".to_string()), rules[0].process);
+		assert_eq!(Some("960".to_string()), rules[0].w);
+	}
+
+	#[test]
+	fn snapshot_relative() {
+		let layout = snapshot(&screens(), true);
+		let rule = &layout.screens[0].windows[0];
+		// The window is at the left edge of the second monitor.
+		assert_eq!(Some("0%".to_string()), rule.x);
+		assert_eq!(Some("50%".to_string()), rule.w);
+		assert_eq!(Some("100%".to_string()), rule.h);
+	}
+
+	#[test]
+	fn snapshot_matches_displays() {
+		let layout = snapshot(&screens(), false);
+		assert_eq!(2, layout.match_displays.len());
+		assert_eq!(
+			Some("10ac5aa04c303531".to_string()),
+			layout.match_displays[0].edid
+		);
+		assert_eq!(Some("DP-1".to_string()), layout.match_displays[1].name);
+		let mut anonymous = screens();
+		anonymous[1].name = None;
+		assert!(snapshot(&anonymous, false).match_displays.is_empty());
+	}
+}
@@ -0,0 +1,432 @@
+use crate::condition::When;
+use crate::layout::{interpolate, Format, Layout, Length, Rect, Screen, Window};
+
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+	Error,
+	Warning,
+}
+
+impl std::fmt::Display for Severity {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Severity::Error => f.pad("error"),
+			Severity::Warning => f.pad("warning"),
+		}
+	}
+}
+
+/// A problem found in a layout, located in the layout when possible.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+	pub severity: Severity,
+	pub message: String,
+	/// The 1-based line and column the problem was found at.
+	pub location: Option<(usize, usize)>,
+}
+
+impl Diagnostic {
+	pub fn error<S: Into<String>>(message: S, location: Option<(usize, usize)>) -> Self {
+		Diagnostic {
+			severity: Severity::Error,
+			message: message.into(),
+			location,
+		}
+	}
+
+	pub fn warning<S: Into<String>>(message: S, location: Option<(usize, usize)>) -> Self {
+		Diagnostic {
+			severity: Severity::Warning,
+			message: message.into(),
+			location,
+		}
+	}
+}
+
+impl std::fmt::Display for Diagnostic {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		if let Some((line, column)) = self.location {
+			write!(f, "{}:{}: ", line, column)?;
+		}
+		write!(f, "{}: {}", self.severity, self.message)
+	}
+}
+
+/// Finds where the fields of a layout are written in its source.
+///
+/// The parsers do not keep track of where values come from, so a field is found by searching for
+/// its key followed by its value, which works the same for every supported format. The same key
+/// and value written several times are told apart by the order they are written in.
+struct Locator<'a> {
+	content: &'a str,
+	occurrences: HashMap<(&'static str, String), usize>,
+}
+
+impl<'a> Locator<'a> {
+	fn new(content: &'a str) -> Self {
+		Locator {
+			content,
+			occurrences: HashMap::new(),
+		}
+	}
+
+	/// Locates the next occurrence of `field` set to `value`.
+	fn next(&mut self, field: &'static str, value: &str) -> Option<(usize, usize)> {
+		let occurrence = self
+			.occurrences
+			.entry((field, value.to_string()))
+			.or_insert(0);
+		let nth = *occurrence;
+		*occurrence += 1;
+		let pattern = format!(
+			r#"(?m)\b{}["']?\s*[:=]\s*(?:Some\(\s*)?["']?{}(?:["')\s,]|$)"#,
+			regex::escape(field),
+			regex::escape(value)
+		);
+		let found = Regex::new(&pattern)
+			.ok()?
+			.find_iter(self.content)
+			.nth(nth)?;
+		let before = &self.content[..found.start()];
+		let line = before.matches('\n').count() + 1;
+		let column = before.len() - before.rfind('\n').map(|i| i + 1).unwrap_or(0) + 1;
+		Some((line, column))
+	}
+}
+
+/// Validates the layout in `content`, written in `format`, without touching any windows.
+///
+/// Besides failing to parse, a layout is invalid if it has lengths that are not lengths, regular
+/// expressions that do not compile, several rules with the same name, or undefined variables.
+/// Screens that match none of the connected `screens`, rules that match nothing and windows
+/// that would be moved entirely off-screen are warned about. `vars` override the variables of
+/// the layout, as when given on the command line.
+///
+/// Variables may be defined by the layouts this layout inherits from, which are not loaded, so
+/// undefined variables are only reported for layouts that inherit from none.
+///
+/// # Examples
+///
+/// ```
+/// # use std::collections::BTreeMap;
+/// # use wlm::layout::Format;
+/// # use wlm::validate::validate;
+/// let yaml = "screens:\n- windows:\n  - title: Inbox\n    w: wide\n";
+/// let diagnostics = validate(yaml, Format::Yaml, &[], &BTreeMap::new());
+/// assert_eq!("4:5: error: Invalid length 'wide' for w", diagnostics[0].to_string());
+/// ```
+pub fn validate(
+	content: &str,
+	format: Format,
+	screens: &[Screen],
+	vars: &BTreeMap<String, String>,
+) -> Vec<Diagnostic> {
+	let layout = match Layout::parse(content, format) {
+		Ok(layout) => layout,
+		Err(e) => return vec![Diagnostic::error(e.message, e.location)],
+	};
+	let mut checker = Checker {
+		locator: Locator::new(content),
+		diagnostics: Vec::new(),
+		names: HashMap::new(),
+		monitors: screens.iter().filter_map(|s| s.bounds).collect(),
+		vars: layout.vars.clone(),
+		inherits: layout.extends.is_some() || !layout.include.is_empty(),
+	};
+	checker.vars.extend(vars.clone());
+	for screen in &layout.screens {
+		checker.when(&screen.when);
+		let monitor = match screen.id {
+			Some(id) => {
+				let location = checker.locator.next("id", &id.to_string());
+				let connected = screens.iter().find(|s| s.id == Some(id));
+				if connected.is_none() && !screens.is_empty() {
+					checker.diagnostics.push(Diagnostic::warning(
+						format!("Screen {} matches no connected monitor", id),
+						location,
+					));
+				}
+				connected.and_then(|s| s.bounds)
+			}
+			None => checker.monitors.first().copied(),
+		};
+		for rule in &screen.windows {
+			checker.rule(rule, monitor);
+		}
+	}
+	checker.diagnostics
+}
+
+struct Checker<'a> {
+	locator: Locator<'a>,
+	diagnostics: Vec<Diagnostic>,
+	names: HashMap<String, usize>,
+	monitors: Vec<Rect>,
+	vars: BTreeMap<String, String>,
+	inherits: bool,
+}
+
+impl Checker<'_> {
+	/// Locates `field` and interpolates its `value`, returning `None` if that fails.
+	fn value(
+		&mut self,
+		field: &'static str,
+		value: &str,
+	) -> Option<(String, Option<(usize, usize)>)> {
+		let location = self.locator.next(field, value);
+		match interpolate(value, &self.vars, |name| std::env::var(name).ok()) {
+			Ok(value) => Some((value, location)),
+			Err(e) => {
+				if !self.inherits {
+					self.diagnostics.push(Diagnostic::error(e, location));
+				}
+				None
+			}
+		}
+	}
+
+	/// Checks the comparisons of a `when` condition, which are the conditions that can be invalid.
+	fn when(&mut self, when: &Option<When>) {
+		let Some(when) = when else { return };
+		for (field, e) in when.errors() {
+			let value = match field {
+				"monitors" => &when.monitors,
+				_ => &when.resolution,
+			};
+			let location = self
+				.locator
+				.next(field, value.as_deref().unwrap_or_default());
+			self.diagnostics.push(Diagnostic::error(e, location));
+		}
+	}
+
+	fn rule(&mut self, rule: &Window, monitor: Option<Rect>) {
+		self.when(&rule.when);
+		if let Some((name, location)) = rule.name.as_ref().and_then(|n| self.value("name", n)) {
+			let count = self.names.entry(name.clone()).or_insert(0);
+			*count += 1;
+			if *count > 1 {
+				self.diagnostics.push(Diagnostic::error(
+					format!("Duplicate rule name '{}'", name),
+					location,
+				));
+			}
+		}
+		for (field, pattern) in [("title", &rule.title), ("process", &rule.process)] {
+			if let Some((pattern, location)) = pattern.as_ref().and_then(|p| self.value(field, p)) {
+				if let Err(e) = Regex::new(&pattern) {
+					let reason = e.to_string();
+					let reason = reason.lines().last().unwrap_or_default().trim();
+					let reason = reason.trim_start_matches("error: ");
+					self.diagnostics.push(Diagnostic::error(
+						format!("Invalid regular expression for {}: {}", field, reason),
+						location,
+					));
+				}
+			}
+		}
+		if rule.title.is_none() && rule.process.is_none() && rule.name.is_none() {
+			self.diagnostics.push(Diagnostic::warning(
+				"Rule matches no window as it has neither a title nor a process",
+				None,
+			));
+		}
+
+		let mut lengths = HashMap::new();
+		let mut x_location = None;
+		for (field, value) in [
+			("x", &rule.x),
+			("y", &rule.y),
+			("w", &rule.w),
+			("h", &rule.h),
+		] {
+			if let Some((value, location)) = value.as_ref().and_then(|v| self.value(field, v)) {
+				if field == "x" {
+					x_location = location;
+				}
+				match value.parse::<Length>() {
+					Ok(length) => {
+						lengths.insert(field, length);
+					}
+					Err(_) => self.diagnostics.push(Diagnostic::error(
+						format!("Invalid length '{}' for {}", value, field),
+						location,
+					)),
+				}
+			}
+		}
+		if let (Some(monitor), Some(x), Some(y)) = (monitor, lengths.get("x"), lengths.get("y")) {
+			let pixels = |field, extent| lengths.get(field).map(|l: &Length| l.to_pixels(extent));
+			let target = Rect::new(
+				x.to_position(monitor.x, monitor.w),
+				y.to_position(monitor.y, monitor.h),
+				pixels("w", monitor.w).unwrap_or(1).max(1),
+				pixels("h", monitor.h).unwrap_or(1).max(1),
+			);
+			if !self.monitors.iter().any(|m| m.intersects(&target)) {
+				self.diagnostics.push(Diagnostic::warning(
+					format!("Window would land entirely off-screen at {}", target),
+					x_location,
+				));
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::layout::ScreenBuilder;
+
+	fn screens() -> Vec<Screen> {
+		vec![
+			ScreenBuilder::default()
+				.id(0)
+				.bounds(Rect::new(0, 0, 1920, 1080))
+				.build()
+				.unwrap(),
+			ScreenBuilder::default()
+				.id(1)
+				.bounds(Rect::new(1920, 0, 2560, 1440))
+				.build()
+				.unwrap(),
+		]
+	}
+
+	fn messages(content: &str, format: Format) -> Vec<String> {
+		validate(content, format, &screens(), &BTreeMap::new())
+			.iter()
+			.map(|d| d.to_string())
+			.collect()
+	}
+
+	#[test]
+	fn valid_layout() {
+		let yaml =
+			"screens:\n- id: 1\n  windows:\n  - process: code\n    x: 1920\n    y: 0\n    w: 50%\n";
+		assert!(messages(yaml, Format::Yaml).is_empty());
+	}
+
+	#[test]
+	fn unknown_field() {
+		let yaml = "screens:\n- windows:\n  - titel: Inbox\n";
+		let messages = messages(yaml, Format::Yaml);
+		assert_eq!(1, messages.len());
+		assert!(messages[0].starts_with("3:5: error: screens[0].windows[0]: unknown field `titel`"));
+	}
+
+	#[test]
+	fn invalid_length() {
+		let json = r#"{"screens": [{"windows": [{"title": "Inbox", "h": "tall"}]}]}"#;
+		assert_eq!(
+			vec!["1:47: error: Invalid length 'tall' for h"],
+			messages(json, Format::Json)
+		);
+	}
+
+	#[test]
+	fn invalid_regex() {
+		let toml = "[[screens]]\n[[screens.windows]]\ntitle = \"Inbox (\"\n";
+		assert_eq!(
+			vec!["3:1: error: Invalid regular expression for title: unclosed group"],
+			messages(toml, Format::Toml)
+		);
+	}
+
+	#[test]
+	fn duplicate_rule_names() {
+		let yaml = "screens:\n- windows:\n  - name: editor\n    process: code\n- windows:\n  - name: editor\n    process: vim\n";
+		assert_eq!(
+			vec!["6:5: error: Duplicate rule name 'editor'"],
+			messages(yaml, Format::Yaml)
+		);
+	}
+
+	#[test]
+	fn same_value_located_in_order() {
+		let yaml =
+			"screens:\n- windows:\n  - process: code\n    w: wide\n  - process: vim\n    w: wide\n";
+		assert_eq!(
+			vec![
+				"4:5: error: Invalid length 'wide' for w",
+				"6:5: error: Invalid length 'wide' for w"
+			],
+			messages(yaml, Format::Yaml)
+		);
+	}
+
+	#[test]
+	fn screen_without_monitor() {
+		let yaml = "screens:\n- id: 2\n  windows:\n  - process: code\n";
+		assert_eq!(
+			vec!["2:3: warning: Screen 2 matches no connected monitor"],
+			messages(yaml, Format::Yaml)
+		);
+	}
+
+	#[test]
+	fn screen_without_monitor_when_monitors_unknown() {
+		let yaml = "screens:\n- id: 2\n  windows:\n  - process: code\n";
+		assert!(validate(yaml, Format::Yaml, &[], &BTreeMap::new()).is_empty());
+	}
+
+	#[test]
+	fn rule_without_selectors() {
+		let yaml = "screens:\n- windows:\n  - w: 50%\n";
+		assert_eq!(
+			vec!["warning: Rule matches no window as it has neither a title nor a process"],
+			messages(yaml, Format::Yaml)
+		);
+	}
+
+	#[test]
+	fn window_off_screen() {
+		let ron = "(screens: [(id: 1, windows: [(process: \"code\", x: \"5000\", y: \"0\", w: \"10%\")])])";
+		assert_eq!(
+			vec!["1:48: warning: Window would land entirely off-screen at 256x1+5000+0"],
+			messages(ron, Format::Ron)
+		);
+	}
+
+	#[test]
+	fn window_partially_on_screen() {
+		let yaml = "screens:\n- windows:\n  - process: code\n    x: -100\n    y: -100\n    w: 200\n    h: 200\n";
+		assert!(messages(yaml, Format::Yaml).is_empty());
+	}
+
+	#[test]
+	fn variables_are_interpolated() {
+		let yaml = "vars:\n  sidebar: 30%\nscreens:\n- windows:\n  - process: code\n    w: ${sidebar}\n    h: ${height}\n";
+		assert_eq!(
+			vec!["7:5: error: Undefined variable 'height'"],
+			messages(yaml, Format::Yaml)
+		);
+		let vars = BTreeMap::from([("height".to_string(), "tall".to_string())]);
+		let diagnostics = validate(yaml, Format::Yaml, &screens(), &vars);
+		assert_eq!(
+			"7:5: error: Invalid length 'tall' for h",
+			diagnostics[0].to_string()
+		);
+	}
+
+	#[test]
+	fn variables_of_inherited_layouts_are_not_reported() {
+		let yaml = "extends: base\nscreens:\n- windows:\n  - process: code\n    w: ${sidebar}\n";
+		assert!(messages(yaml, Format::Yaml).is_empty());
+	}
+
+	#[test]
+	fn invalid_conditions() {
+		let yaml = "screens:\n- when:\n    monitors: two\n  windows:\n  - process: code\n    when: { resolution: 4k, hostname: 'work-*' }\n";
+		assert_eq!(
+			vec![
+				"3:5: error: Invalid monitor count 'two'",
+				"6:13: error: Invalid resolution '4k'"
+			],
+			messages(yaml, Format::Yaml)
+		);
+	}
+}